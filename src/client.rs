@@ -1,12 +1,22 @@
 use std::{
     collections::{BTreeMap, HashMap},
+    fs::File,
+    io::BufReader,
+    sync::Arc,
     time::Duration,
 };
 
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_derive::{Deserialize, Serialize};
-use ureq::{Agent, AgentBuilder};
+use ureq::{Agent, AgentBuilder, Request};
+use zeroize::Zeroize;
 
 use crate::error::{Error, Result};
 
@@ -26,13 +36,69 @@ pub struct VaultResponse<D> {
     pub warnings: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct VaultSecret {
+    #[serde(skip_serializing_if = "Option::is_none")]
     secret: Option<String>,
     #[serde(flatten)]
     other: BTreeMap<String, serde_json::Value>,
 }
 
+impl VaultSecret {
+    /// A secret with a single value, like the ones written by the browser.
+    pub fn new(value: &str) -> Self {
+        Self {
+            secret: Some(value.to_string()),
+            other: BTreeMap::new(),
+        }
+    }
+
+    /// The value of a secret made of the single `secret` field, the only kind edited as a line.
+    pub fn single_value(&self) -> Option<&str> {
+        match self.other.is_empty() {
+            true => self.secret.as_deref(),
+            false => None,
+        }
+    }
+
+    /// Every field of the secret, the values that are not strings are shown as JSON.
+    pub fn fields(&self) -> BTreeMap<String, String> {
+        let mut fields: BTreeMap<String, String> = self
+            .other
+            .iter()
+            .map(|(k, v)| {
+                let value = match v {
+                    serde_json::Value::String(s) => s.clone(),
+                    v => v.to_string(),
+                };
+                (k.clone(), value)
+            })
+            .collect();
+        if let Some(secret) = &self.secret {
+            fields.insert("secret".to_owned(), secret.clone());
+        }
+
+        fields
+    }
+}
+
+/// Wipe the values, the names of the fields are kept.
+impl Zeroize for VaultSecret {
+    fn zeroize(&mut self) {
+        fn zeroize_value(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::String(s) => s.zeroize(),
+                serde_json::Value::Array(values) => values.iter_mut().for_each(zeroize_value),
+                serde_json::Value::Object(map) => map.values_mut().for_each(zeroize_value),
+                _ => {}
+            }
+        }
+
+        self.secret.zeroize();
+        self.other.values_mut().for_each(zeroize_value);
+    }
+}
+
 impl From<&VaultSecret> for String {
     fn from(val: &VaultSecret) -> Self {
         match &val.secret {
@@ -42,6 +108,38 @@ impl From<&VaultSecret> for String {
     }
 }
 
+/// Secret as stored by the KV v2 engine, the actual secret is nested in `data`.
+#[derive(Serialize, Deserialize, Debug)]
+struct KvV2Secret<T> {
+    data: T,
+}
+
+/// Metadata of a KV v2 key, read from `<mount>/metadata/<path>`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct KvMetadata {
+    pub current_version: u64,
+    pub created_time: String,
+    pub updated_time: String,
+    pub custom_metadata: Option<BTreeMap<String, String>>,
+    /// 0 keeps every version
+    pub max_versions: u64,
+    /// Duration such as `768h0m0s`, `0s` keeps the versions forever
+    pub delete_version_after: String,
+}
+
+/// A secret with everything vault sent along with it.
+#[derive(Debug)]
+pub struct SecretDetails {
+    pub secret: VaultSecret,
+    pub version: KvVersion,
+    pub request_id: String,
+    pub lease_id: Option<String>,
+    pub renewable: Option<bool>,
+    pub warnings: Vec<String>,
+    /// `None` for KV v1, or when the token cannot read the metadata of the key
+    pub metadata: Option<KvMetadata>,
+}
+
 /// Response sent by vault when issuing a `LIST` request.
 #[derive(Deserialize, Debug)]
 pub struct ListResponse {
@@ -49,6 +147,36 @@ pub struct ListResponse {
     pub keys: Vec<String>,
 }
 
+/// Mount information returned by `sys/internal/ui/mounts`.
+#[derive(Deserialize, Debug)]
+struct MountResponse {
+    path: String,
+    options: Option<HashMap<String, String>>,
+}
+
+/// Version of the KV secrets engine behind a mount.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum KvVersion {
+    V1,
+    V2,
+}
+
+#[derive(Clone, Debug)]
+pub struct Mount {
+    /// Path of the mount, always ends with a `/`
+    pub path: String,
+    pub version: KvVersion,
+}
+
+/// How to delete a secret, only relevant for KV v2.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum DeleteMode {
+    /// Delete the latest version, it can still be undeleted
+    Soft,
+    /// Delete all the versions and the metadata of the key
+    Metadata,
+}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum FromCache {
     Yes,
@@ -69,28 +197,170 @@ pub trait HttpClient {
         body: Option<TBody>,
     ) -> Result<()>;
     fn clear_cache(&mut self);
+    /// Number of responses kept in the cache
+    fn cached(&self) -> usize;
+    fn address(&self) -> &str;
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenLookup {
+    ttl: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Deserialize, Debug)]
+struct LoginAuth {
+    client_token: String,
+}
+
+/// Accept any certificate, for the servers with a self-signed certificate and no CA at hand.
+#[derive(Debug)]
+struct NoCertificateVerification;
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &ring::default_provider().signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &ring::default_provider().signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        ring::default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
 }
 
+#[derive(Clone)]
 pub struct UreqClient {
     client: Agent,
     vault_addr: String,
     token: String,
+    namespace: Option<String>,
     cache: HashMap<String, String>,
 }
 
 impl UreqClient {
     pub fn new(addr: &str, token: &str) -> Self {
-        let client = AgentBuilder::new()
-            .timeout_read(Duration::from_secs(5))
-            .timeout_write(Duration::from_secs(5))
-            .build();
         Self {
-            client,
+            client: Self::agent().build(),
             vault_addr: addr.to_string(),
             token: token.into(),
+            namespace: None,
             cache: HashMap::new(),
         }
     }
+
+    fn agent() -> AgentBuilder {
+        AgentBuilder::new()
+            .timeout_read(Duration::from_secs(5))
+            .timeout_write(Duration::from_secs(5))
+    }
+
+    /// Send every request to this vault enterprise namespace.
+    pub fn with_namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    /// Trust the certificates of the PEM file `ca_cert` on top of the usual ones, or trust
+    /// every certificate with `skip_verify`.
+    pub fn with_tls(mut self, ca_cert: Option<&str>, skip_verify: bool) -> Result<Self> {
+        let config = if skip_verify {
+            ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoCertificateVerification))
+                .with_no_client_auth()
+        } else if let Some(ca_cert) = ca_cert {
+            let mut roots = RootCertStore::empty();
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            for cert in rustls_pemfile::certs(&mut BufReader::new(File::open(ca_cert)?)) {
+                roots.add(cert?).map_err(|err| {
+                    Error::Application(format!("invalid CA certificate '{}': {}", ca_cert, err))
+                })?;
+            }
+            ClientConfig::builder()
+                .with_root_certificates(roots)
+                .with_no_client_auth()
+        } else {
+            return Ok(self);
+        };
+
+        self.client = Self::agent().tls_config(Arc::new(config)).build();
+        Ok(self)
+    }
+
+    /// Exchange the role and secret IDs for a token with the AppRole auth method.
+    pub fn login_approle(&mut self, mount: &str, role_id: &str, secret_id: &str) -> Result<()> {
+        let body = serde_json::json!({ "role_id": role_id, "secret_id": secret_id });
+        let res = self
+            .request(
+                "POST",
+                &format!("v1/auth/{}/login", mount.trim_matches('/')),
+            )
+            .send_string(&body.to_string())
+            .map_err(|err| match err {
+                ureq::Error::Status(code, _) => Error::Application(format!(
+                    "the AppRole login failed with status {}, check the role and secret IDs",
+                    code
+                )),
+                err => Error::Ureq(Box::new(err)),
+            })?;
+        let res: LoginResponse = serde_json::from_str(&res.into_string()?)?;
+        self.token = res.auth.client_token;
+
+        Ok(())
+    }
+
+    fn request(&self, method: &str, path: &str) -> Request {
+        let request = self
+            .client
+            .request(method, &format!("{}/{}", self.vault_addr, path))
+            .set("X-Vault-Token", &self.token)
+            .set("Content-Type", "application/json");
+
+        match self.namespace.as_ref() {
+            Some(namespace) => request.set("X-Vault-Namespace", namespace),
+            None => request,
+        }
+    }
 }
 
 impl HttpClient for UreqClient {
@@ -107,19 +377,14 @@ impl HttpClient for UreqClient {
             }
         }
 
-        match self
-            .client
-            .request(method, &format!("{}/{}", self.vault_addr, path))
-            .set("X-Vault-Token", &self.token)
-            .set("Content-Type", "application/json")
-            .call()
-        {
+        match self.request(method, path).call() {
             Ok(res) => {
                 let res = res.into_string()?;
                 self.cache.insert(cache_key, res.clone());
 
                 Ok(serde_json::from_str(&res)?)
             }
+            Err(ureq::Error::Status(404, _)) => Err(Error::NotFound(path.to_string())),
             Err(err) => Err(Error::Ureq(Box::new(err))),
         }
     }
@@ -130,11 +395,7 @@ impl HttpClient for UreqClient {
         path: &str,
         body: Option<TBody>,
     ) -> Result<()> {
-        let query = self
-            .client
-            .request(method, &format!("{}/{}", self.vault_addr, path))
-            .set("X-Vault-Token", &self.token)
-            .set("Content-Type", "application/json");
+        let query = self.request(method, path);
 
         let res = match body {
             Some(body) => query.send_string(&serde_json::to_string(&body)?),
@@ -143,6 +404,7 @@ impl HttpClient for UreqClient {
 
         match res {
             Ok(_) => Ok(()),
+            Err(ureq::Error::Status(404, _)) => Err(Error::NotFound(path.to_string())),
             Err(err) => Err(Error::Ureq(Box::new(err))),
         }
     }
@@ -150,9 +412,112 @@ impl HttpClient for UreqClient {
     fn clear_cache(&mut self) {
         self.cache.clear();
     }
+
+    fn cached(&self) -> usize {
+        self.cache.len()
+    }
+
+    fn address(&self) -> &str {
+        &self.vault_addr
+    }
 }
 
-pub struct MockClient {}
+/// In-memory vault used for the `mock/` demo root and the tests.
+///
+/// `mock/` behaves like a KV v1 mount and `mockv2/` like a KV v2 mount.
+#[derive(Clone)]
+pub struct MockClient {
+    /// Secrets indexed by their logical path, kept in insertion order
+    secrets: Vec<(String, VaultSecret)>,
+    /// KV v2 keys whose latest version was deleted, they are still listed but cannot be read
+    soft_deleted: Vec<String>,
+    /// Current version of the KV v2 keys, increased by each write
+    versions: HashMap<String, u64>,
+    /// Paths where writing fails, to exercise the error handling
+    denied: Vec<String>,
+}
+
+impl MockClient {
+    pub fn new() -> Self {
+        let mut secrets = vec![("mock/key1/key".to_string(), Self::secret("value"))];
+        for i in 2..15 {
+            let path = match i {
+                9 => "mock/key9/nested".to_string(),
+                _ => format!("mock/key{}", i),
+            };
+            secrets.push((path, Self::secret("value")));
+        }
+        secrets.push(("mock/key9/other".to_string(), Self::secret("value")));
+        secrets.push(("mock/key15/deep/key".to_string(), Self::secret("value")));
+        secrets.push(("mockv2/app/key".to_string(), Self::secret("value")));
+
+        Self {
+            secrets,
+            soft_deleted: vec![],
+            versions: HashMap::new(),
+            denied: vec![],
+        }
+    }
+
+    #[cfg(test)]
+    pub fn deny_writes(mut self, prefix: &str) -> Self {
+        self.denied.push(prefix.to_string());
+        self
+    }
+
+    fn secret(value: &str) -> VaultSecret {
+        VaultSecret {
+            secret: Some(value.to_string()),
+            other: BTreeMap::new(),
+        }
+    }
+
+    fn is_v2(path: &str) -> bool {
+        path.starts_with("mockv2/")
+    }
+
+    /// Strip the api prefix and the KV v2 `data/` or `metadata/` segment.
+    fn logical_path(path: &str) -> String {
+        let path = path.trim_start_matches("v1/");
+        if Self::is_v2(path) {
+            let rest = &path["mockv2/".len()..];
+            let rest = rest
+                .strip_prefix("data/")
+                .or_else(|| rest.strip_prefix("metadata/"))
+                .unwrap_or(rest);
+            return format!("mockv2/{}", rest);
+        }
+
+        path.to_string()
+    }
+
+    fn version(&self, key: &str) -> u64 {
+        self.versions.get(key).copied().unwrap_or(1)
+    }
+
+    fn list(&self, path: &str) -> Vec<String> {
+        let mut keys: Vec<String> = vec![];
+        for (key, _) in &self.secrets {
+            if let Some(rest) = key.strip_prefix(path) {
+                let child = match rest.find('/') {
+                    Some(i) => &rest[..=i],
+                    None => rest,
+                };
+                if !keys.iter().any(|k| k == child) {
+                    keys.push(child.to_string());
+                }
+            }
+        }
+
+        keys
+    }
+}
+
+impl Default for MockClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl HttpClient for MockClient {
     fn read<T: DeserializeOwned>(
@@ -161,17 +526,62 @@ impl HttpClient for MockClient {
         path: &str,
         _cache: FromCache,
     ) -> Result<VaultResponse<T>> {
+        let data = match method {
+            "GET" if path.starts_with("v1/sys/internal/ui/mounts/") => {
+                let mount = path.trim_start_matches("v1/sys/internal/ui/mounts/");
+                match Self::is_v2(mount) {
+                    true => serde_json::json!({"path": "mockv2/", "options": {"version": "2"}}),
+                    false => serde_json::json!({"path": "mock/", "options": null}),
+                }
+            }
+            "GET" if path == "v1/auth/token/lookup-self" => serde_json::json!({"ttl": 0}),
+            "GET" if path.starts_with("v1/mockv2/metadata/") => {
+                let key = Self::logical_path(path);
+                if !self.secrets.iter().any(|(k, _)| *k == key) {
+                    return Err(Error::NotFound(path.to_string()));
+                }
+                serde_json::json!({
+                    "current_version": self.version(&key),
+                    "created_time": "2024-01-01T00:00:00Z",
+                    "updated_time": "2024-01-01T00:00:00Z",
+                    "custom_metadata": {"owner": "mock"},
+                    "max_versions": 0,
+                    "delete_version_after": "0s",
+                })
+            }
+            "GET" => {
+                let key = Self::logical_path(path);
+                let secret = match self.secrets.iter().find(|(k, _)| *k == key) {
+                    Some((_, secret)) if !self.soft_deleted.contains(&key) => {
+                        serde_json::to_value(secret)?
+                    }
+                    _ => return Err(Error::NotFound(path.to_string())),
+                };
+                match Self::is_v2(&key) {
+                    true => serde_json::json!({
+                        "data": secret,
+                        "metadata": {"version": self.version(&key)}
+                    }),
+                    false => secret,
+                }
+            }
+            "LIST" => {
+                let keys = self.list(&Self::logical_path(path));
+                if keys.is_empty() {
+                    return Err(Error::NotFound(path.to_string()));
+                }
+                serde_json::json!({ "keys": keys })
+            }
+            _ => serde_json::Value::Null,
+        };
+
         Ok(VaultResponse {
             request_id: "mock".to_string(),
             lease_id: None,
             renewable: None,
-            data: match method {
-                "GET" => Some(serde_json::from_str("{\"secret\":\"value\"}")?),
-                "LIST" => Some(match path {
-                    "v1/mock/key1/" => serde_json::from_str("{\"keys\":[\"key\"]}")?,
-                    _ => serde_json::from_str("{\"keys\":[\"key1/\",\"key2\",\"key3\",\"key4\",\"key5\",\"key6\",\"key7\",\"key8\",\"key9/\",\"key10\",\"key11\",\"key12\",\"key13\",\"key14\",\"key15/\"]}")?,
-                }),
-                _ => None,
+            data: match data {
+                serde_json::Value::Null => None,
+                data => Some(serde_json::from_value(data)?),
             },
             warnings: None,
         })
@@ -179,23 +589,155 @@ impl HttpClient for MockClient {
 
     fn write<TBody: Serialize>(
         &mut self,
-        _method: &str,
-        _path: &str,
-        _body: Option<TBody>,
+        method: &str,
+        path: &str,
+        body: Option<TBody>,
     ) -> Result<()> {
+        let key = Self::logical_path(path);
+        if self.denied.iter().any(|d| key.starts_with(d)) {
+            return Err(Error::Vault("permission denied".to_string()));
+        }
+
+        match method {
+            "POST" => {
+                let mut body = serde_json::to_value(body)?;
+                if Self::is_v2(&key) {
+                    body = body["data"].take();
+                }
+                let secret: VaultSecret = serde_json::from_value(body)?;
+                self.soft_deleted.retain(|k| *k != key);
+                if Self::is_v2(&key) {
+                    let version = match self.secrets.iter().any(|(k, _)| *k == key) {
+                        true => self.version(&key) + 1,
+                        false => 1,
+                    };
+                    self.versions.insert(key.clone(), version);
+                }
+                match self.secrets.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, old)) => *old = secret,
+                    None => self.secrets.push((key, secret)),
+                }
+            }
+            "DELETE" => {
+                if !self.secrets.iter().any(|(k, _)| *k == key) {
+                    return Err(Error::NotFound(path.to_string()));
+                }
+                if path.starts_with("v1/mockv2/data/") {
+                    self.soft_deleted.push(key);
+                } else {
+                    self.soft_deleted.retain(|k| *k != key);
+                    self.versions.remove(&key);
+                    self.secrets.retain(|(k, _)| *k != key);
+                }
+            }
+            _ => (),
+        }
+
         Ok(())
     }
 
     fn clear_cache(&mut self) {}
+
+    fn cached(&self) -> usize {
+        0
+    }
+
+    fn address(&self) -> &str {
+        "mock"
+    }
 }
 
+#[derive(Clone)]
 pub struct VaultClient<H: HttpClient> {
     client: H,
+    mounts: Vec<Mount>,
 }
 
 impl<H: HttpClient> VaultClient<H> {
     pub fn new(client: H) -> Self {
-        Self { client }
+        Self {
+            client,
+            mounts: vec![],
+        }
+    }
+
+    /// Use these mounts instead of asking vault, e.g. when the token cannot read the mount
+    /// information of a KV v2 mount.
+    pub fn with_mounts(mut self, mounts: Vec<Mount>) -> Self {
+        self.mounts = mounts;
+        self
+    }
+
+    /// Find the mount containing `path`, asking vault the first time a mount is seen.
+    ///
+    /// When the token is not allowed to read the mount information, the first segment of
+    /// the path is assumed to be a KV v1 mount.
+    pub fn mount(&mut self, path: &str) -> Mount {
+        if let Some(mount) = self.mounts.iter().find(|m| path.starts_with(&m.path)) {
+            return mount.clone();
+        }
+
+        let mount = match self.client.read::<MountResponse>(
+            "GET",
+            &format!("v1/sys/internal/ui/mounts/{}", path),
+            FromCache::Yes,
+        ) {
+            Ok(VaultResponse {
+                data: Some(res), ..
+            }) => Mount {
+                version: match res.options.as_ref().and_then(|o| o.get("version")) {
+                    Some(v) if v == "2" => KvVersion::V2,
+                    _ => KvVersion::V1,
+                },
+                path: res.path,
+            },
+            _ => Mount {
+                path: match path.find('/') {
+                    Some(i) => path[..=i].to_string(),
+                    None => format!("{}/", path),
+                },
+                version: KvVersion::V1,
+            },
+        };
+        self.mounts.push(mount.clone());
+
+        mount
+    }
+
+    /// Build the api path of `path`, inserting `segment` after the mount for KV v2.
+    fn api_path(&mut self, path: &str, segment: &str) -> String {
+        let mount = self.mount(path);
+        match mount.version {
+            KvVersion::V1 => format!("v1/{}", path),
+            KvVersion::V2 => format!(
+                "v1/{}{}/{}",
+                mount.path,
+                segment,
+                &path[mount.path.len().min(path.len())..]
+            ),
+        }
+    }
+
+    /// Read a secret, the KV v2 wrapping is removed from the data of the response.
+    fn read_secret<T: DeserializeOwned>(
+        &mut self,
+        path: &str,
+        cache: FromCache,
+    ) -> Result<VaultResponse<T>> {
+        let api_path = self.api_path(path, "data");
+        match self.mount(path).version {
+            KvVersion::V1 => self.client.read::<T>("GET", &api_path, cache),
+            KvVersion::V2 => {
+                let res = self.client.read::<KvV2Secret<T>>("GET", &api_path, cache)?;
+                Ok(VaultResponse {
+                    request_id: res.request_id,
+                    lease_id: res.lease_id,
+                    renewable: res.renewable,
+                    data: res.data.map(|d| d.data),
+                    warnings: res.warnings,
+                })
+            }
+        }
     }
 
     pub fn get_secret<T: DeserializeOwned + std::fmt::Debug>(
@@ -203,9 +745,7 @@ impl<H: HttpClient> VaultClient<H> {
         path: &str,
         cache: FromCache,
     ) -> Result<T> {
-        let res = self
-            .client
-            .read::<T>("GET", &format!("v1/{}", path), cache)?;
+        let res = self.read_secret::<T>(path, cache)?;
         match res.data {
             Some(data) => Ok(data),
             None => Err(Error::Vault(format!(
@@ -215,8 +755,70 @@ impl<H: HttpClient> VaultClient<H> {
         }
     }
 
+    /// Read the metadata of a key, `None` for KV v1 which has no metadata.
+    pub fn get_metadata(&mut self, path: &str, cache: FromCache) -> Result<Option<KvMetadata>> {
+        if self.mount(path).version == KvVersion::V1 {
+            return Ok(None);
+        }
+
+        let api_path = self.api_path(path, "metadata");
+        let res = self.client.read::<KvMetadata>("GET", &api_path, cache)?;
+        match res.data {
+            Some(data) => Ok(Some(data)),
+            None => Err(Error::Vault(format!(
+                "Vault response did not contain data: {:?}",
+                res
+            ))),
+        }
+    }
+
+    /// Read a secret without the cache, with the metadata of the key for KV v2.
+    pub fn get_secret_details(&mut self, path: &str) -> Result<SecretDetails> {
+        let res = self.read_secret::<VaultSecret>(path, FromCache::No)?;
+        let secret = match res.data {
+            Some(data) => data,
+            None => {
+                return Err(Error::Vault(format!(
+                    "Vault response did not contain data: {:?}",
+                    res
+                )))
+            }
+        };
+
+        let version = self.mount(path).version;
+        let mut warnings = res.warnings.unwrap_or_default();
+        let metadata = match version {
+            KvVersion::V1 => None,
+            KvVersion::V2 => {
+                let api_path = self.api_path(path, "metadata");
+                // reading the secret does not require to be allowed to read its metadata
+                match self
+                    .client
+                    .read::<KvMetadata>("GET", &api_path, FromCache::No)
+                {
+                    Ok(res) => {
+                        warnings.extend(res.warnings.unwrap_or_default());
+                        res.data
+                    }
+                    Err(_) => None,
+                }
+            }
+        };
+
+        Ok(SecretDetails {
+            secret,
+            version,
+            request_id: res.request_id,
+            lease_id: res.lease_id.filter(|id| !id.is_empty()),
+            renewable: res.renewable,
+            warnings,
+            metadata,
+        })
+    }
+
     pub fn list_secrets(&mut self, path: &str, cache: FromCache) -> Result<ListResponse> {
-        let res = self.client.read("LIST", &format!("v1/{}", path), cache)?;
+        let api_path = self.api_path(path, "metadata");
+        let res = self.client.read("LIST", &api_path, cache)?;
         match res.data {
             Some(data) => Ok(data),
             None => Err(Error::Vault(format!(
@@ -226,23 +828,56 @@ impl<H: HttpClient> VaultClient<H> {
         }
     }
 
+    #[cfg(test)]
     pub fn write_secret(&mut self, path: &str, secret: &str) -> Result<()> {
-        self.client.write(
-            "POST",
-            &format!("v1/{}", path),
-            Some(VaultSecret {
-                secret: Some(secret.to_string()),
-                other: BTreeMap::new(),
-            }),
-        )
+        self.write_secret_data(path, &VaultSecret::new(secret))
     }
 
+    /// Write a secret with all its fields, wrapping it in `data` for KV v2.
+    pub fn write_secret_data(&mut self, path: &str, secret: &VaultSecret) -> Result<()> {
+        let api_path = self.api_path(path, "data");
+        match self.mount(path).version {
+            KvVersion::V1 => self.client.write("POST", &api_path, Some(secret)),
+            KvVersion::V2 => {
+                self.client
+                    .write("POST", &api_path, Some(KvV2Secret { data: secret }))
+            }
+        }
+    }
+
+    /// Delete a secret, for KV v2 only its latest version is deleted and it can be undeleted.
     pub fn delete_secret(&mut self, path: &str) -> Result<()> {
-        self.client
-            .write::<()>("DELETE", &format!("v1/{}", path), None)
+        self.delete_secret_with(path, DeleteMode::Soft)
+    }
+
+    pub fn delete_secret_with(&mut self, path: &str, mode: DeleteMode) -> Result<()> {
+        let api_path = match mode {
+            DeleteMode::Soft => self.api_path(path, "data"),
+            DeleteMode::Metadata => self.api_path(path, "metadata"),
+        };
+        self.client.write::<()>("DELETE", &api_path, None)
     }
 
     pub fn clear_cache(&mut self) {
         self.client.clear_cache();
     }
+
+    pub fn cached(&self) -> usize {
+        self.client.cached()
+    }
+
+    pub fn address(&self) -> &str {
+        self.client.address()
+    }
+
+    /// Seconds before the token expires, 0 for a token that never expires.
+    pub fn token_ttl(&mut self) -> Result<u64> {
+        let res: VaultResponse<TokenLookup> =
+            self.client
+                .read("GET", "v1/auth/token/lookup-self", FromCache::No)?;
+        match res.data {
+            Some(lookup) => Ok(lookup.ttl),
+            None => Err(Error::Application("empty token lookup".to_owned())),
+        }
+    }
 }
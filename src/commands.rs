@@ -0,0 +1,477 @@
+use std::{
+    borrow::Cow,
+    io::{stdout, IsTerminal},
+};
+
+use gumdrop::Options;
+use regex::Regex;
+
+use crate::{
+    client::{FromCache, HttpClient, MockClient, UreqClient, VaultClient},
+    config::{Config, Connection},
+    diff::{changed_fields, TreeDiff},
+    error::{Error, Result},
+    fuzzy::fuzzy_filter,
+    grep::{grep_tree, DEFAULT_CONCURRENCY},
+    render::escape,
+    sync::{self, SyncAction, SyncMode},
+    tree::list_keys_recursive,
+};
+
+/// Escape the control characters of the keys and values printed to a terminal, the output of a
+/// pipe is left as is.
+fn printable(text: &str) -> Cow<'_, str> {
+    match stdout().is_terminal() {
+        true => escape(text),
+        false => Cow::Borrowed(text),
+    }
+}
+
+/// Commands run without the interactive browser, e.g. `vw sync <src> <dst>`.
+#[derive(Options)]
+pub enum Command {
+    #[options(help = "copy the keys of a directory to another one, possibly on another server")]
+    Sync(SyncArgs),
+    #[options(help = "compare two directories key by key, possibly on different servers")]
+    Diff(DiffArgs),
+    #[options(help = "fuzzy search of the keys below a directory")]
+    Find(FindArgs),
+    #[options(help = "search the secret values below a directory with a regex")]
+    Grep(GrepArgs),
+}
+
+#[derive(Options)]
+pub struct SyncArgs {
+    #[options(help_flag)]
+    help: bool,
+
+    #[options(free, required, help = "Source directory")]
+    source: String,
+
+    #[options(free, required, help = "Destination directory")]
+    destination: String,
+
+    #[options(
+        help = "add-only, update (also overwrite the changed keys) or mirror (also delete the extra keys), defaults to add-only",
+        meta = "MODE"
+    )]
+    mode: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Write the changes, without it only the plan is shown"
+    )]
+    apply: bool,
+
+    #[options(
+        no_short,
+        help = "Profile of the config file for the source",
+        meta = "NAME"
+    )]
+    src_profile: Option<String>,
+
+    #[options(
+        no_short,
+        help = "URL of the source vault server, defaults to $VAULT_ADDR"
+    )]
+    src_host: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Token for the source, default to the value in ~/.vault-token"
+    )]
+    src_token: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Profile of the config file for the destination",
+        meta = "NAME"
+    )]
+    dst_profile: Option<String>,
+
+    #[options(
+        no_short,
+        help = "URL of the destination vault server, defaults to $VAULT_ADDR"
+    )]
+    dst_host: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Token for the destination, default to the value in ~/.vault-token"
+    )]
+    dst_token: Option<String>,
+}
+
+#[derive(Options)]
+pub struct DiffArgs {
+    #[options(help_flag)]
+    help: bool,
+
+    #[options(free, required, help = "First directory")]
+    left: String,
+
+    #[options(free, required, help = "Second directory")]
+    right: String,
+
+    #[options(no_short, help = "Print the values of the fields that differ")]
+    show_values: bool,
+
+    #[options(
+        no_short,
+        help = "Profile of the config file for the first directory",
+        meta = "NAME"
+    )]
+    left_profile: Option<String>,
+
+    #[options(
+        no_short,
+        help = "URL of the vault server of the first directory, defaults to $VAULT_ADDR"
+    )]
+    left_host: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Token for the first directory, default to the value in ~/.vault-token"
+    )]
+    left_token: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Profile of the config file for the second directory",
+        meta = "NAME"
+    )]
+    right_profile: Option<String>,
+
+    #[options(
+        no_short,
+        help = "URL of the vault server of the second directory, defaults to $VAULT_ADDR"
+    )]
+    right_host: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Token for the second directory, default to the value in ~/.vault-token"
+    )]
+    right_token: Option<String>,
+}
+
+#[derive(Options)]
+pub struct FindArgs {
+    #[options(help_flag)]
+    help: bool,
+
+    #[options(free, required, help = "Pattern to search, e.g. stripewh")]
+    pattern: String,
+
+    #[options(free, required, help = "Directory to search")]
+    root_path: String,
+
+    #[options(help = "Maximum number of results, defaults to all", meta = "N")]
+    limit: Option<usize>,
+
+    #[options(help = "Profile of the config file to use", short = "P")]
+    profile: Option<String>,
+
+    #[options(help = "URL of the vault server, defaults to $VAULT_ADDR", short = "H")]
+    host: Option<String>,
+
+    #[options(help = "Vault token, default to the value in ~/.vault-token")]
+    token: Option<String>,
+}
+
+#[derive(Options)]
+pub struct GrepArgs {
+    #[options(help_flag)]
+    help: bool,
+
+    #[options(
+        free,
+        required,
+        help = "Regex to search in the values, e.g. old-db\\.internal"
+    )]
+    pattern: String,
+
+    #[options(free, required, help = "Directory to search")]
+    root_path: String,
+
+    #[options(no_short, help = "Print the values of the matching fields")]
+    show_values: bool,
+
+    #[options(
+        no_short,
+        help = "Number of secrets read at the same time, defaults to 8",
+        meta = "N"
+    )]
+    concurrency: Option<usize>,
+
+    #[options(help = "Profile of the config file to use", short = "P")]
+    profile: Option<String>,
+
+    #[options(help = "URL of the vault server, defaults to $VAULT_ADDR", short = "H")]
+    host: Option<String>,
+
+    #[options(help = "Vault token, default to the value in ~/.vault-token")]
+    token: Option<String>,
+}
+
+pub fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Sync(args) => run_sync(args),
+        Command::Diff(args) => run_diff(args),
+        Command::Find(args) => run_find(args),
+        Command::Grep(args) => run_grep(args),
+    }
+}
+
+fn as_directory(path: &str) -> String {
+    match path.ends_with('/') {
+        true => path.to_owned(),
+        false => format!("{}/", path),
+    }
+}
+
+/// Client for one side of a command, see `Connection::resolve` for the precedence.
+fn connect(
+    profile: Option<String>,
+    host: Option<String>,
+    token: Option<String>,
+) -> Result<VaultClient<UreqClient>> {
+    Connection::resolve(&Config::load()?, profile.as_deref(), host, token)?.connect()
+}
+
+/// The demo mounts are served by the mock client, like in the browser.
+pub fn is_mock(path: &str) -> bool {
+    path.starts_with("mock/") || path.starts_with("mockv2/")
+}
+
+fn run_sync(args: SyncArgs) -> Result<()> {
+    let mode: SyncMode = args.mode.as_deref().unwrap_or("add-only").parse()?;
+    let source_root = as_directory(&args.source);
+    let destination_root = as_directory(&args.destination);
+
+    if is_mock(&source_root) && is_mock(&destination_root) {
+        return sync_with(
+            VaultClient::new(MockClient::new()),
+            &source_root,
+            VaultClient::new(MockClient::new()),
+            &destination_root,
+            mode,
+            args.apply,
+        );
+    }
+
+    sync_with(
+        connect(args.src_profile, args.src_host, args.src_token)?,
+        &source_root,
+        connect(args.dst_profile, args.dst_host, args.dst_token)?,
+        &destination_root,
+        mode,
+        args.apply,
+    )
+}
+
+fn sync_with<S: HttpClient + Send, D: HttpClient + Send>(
+    mut source: VaultClient<S>,
+    source_root: &str,
+    mut destination: VaultClient<D>,
+    destination_root: &str,
+    mode: SyncMode,
+    apply: bool,
+) -> Result<()> {
+    let diff = TreeDiff::compute(&mut source, source_root, &mut destination, destination_root)?;
+    let actions = sync::plan(&diff, mode);
+
+    for (key, action) in &actions {
+        let (marker, description) = action.describe();
+        println!(
+            "{} {} ({})",
+            marker,
+            printable(&format!("{}{}", destination_root, key)),
+            description
+        );
+    }
+    let count = |a: SyncAction| actions.iter().filter(|(_, b)| *b == a).count();
+    println!(
+        "{} to add, {} to update, {} to delete, {} unchanged",
+        count(SyncAction::Add),
+        count(SyncAction::Update),
+        count(SyncAction::Delete),
+        diff.unchanged.len()
+    );
+
+    if !apply {
+        println!("dry run, nothing was written, use --apply to sync");
+        return Ok(());
+    }
+
+    let report = sync::apply(&diff, &actions, &mut destination, destination_root);
+    println!(
+        "{} keys written, {} keys deleted",
+        report.written, report.deleted
+    );
+    for (path, err) in &report.failed {
+        eprintln!("failed to sync '{}': {}", path, err);
+    }
+
+    match report.failed.len() {
+        0 => Ok(()),
+        n => Err(Error::Application(format!(
+            "{} keys could not be synced",
+            n
+        ))),
+    }
+}
+
+fn run_diff(args: DiffArgs) -> Result<()> {
+    let left_root = as_directory(&args.left);
+    let right_root = as_directory(&args.right);
+
+    let diff = if is_mock(&left_root) && is_mock(&right_root) {
+        TreeDiff::compute(
+            &mut VaultClient::new(MockClient::new()),
+            &left_root,
+            &mut VaultClient::new(MockClient::new()),
+            &right_root,
+        )?
+    } else {
+        TreeDiff::compute(
+            &mut connect(args.left_profile, args.left_host, args.left_token)?,
+            &left_root,
+            &mut connect(args.right_profile, args.right_host, args.right_token)?,
+            &right_root,
+        )?
+    };
+
+    let mut lines: Vec<(&String, &str)> = diff
+        .only_left
+        .iter()
+        .map(|k| (k, "<"))
+        .chain(diff.only_right.iter().map(|k| (k, ">")))
+        .chain(diff.changed.iter().map(|k| (k, "~")))
+        .collect();
+    lines.sort();
+
+    for (key, marker) in lines {
+        let key = printable(key);
+        match marker {
+            "<" => println!("< {} (only in {})", key, left_root),
+            ">" => println!("> {} (only in {})", key, right_root),
+            _ => {
+                let (left, right) = (&diff.left_values[&*key], &diff.right_values[&*key]);
+                let fields = changed_fields(left, right);
+                println!("~ {} (differs: {})", key, printable(&fields.join(", ")));
+                if args.show_values {
+                    let (left, right) = (left.fields(), right.fields());
+                    for field in fields {
+                        println!(
+                            "    {}: {} | {}",
+                            printable(&field),
+                            printable(left.get(&field).map_or("(missing)", |v| v.as_str())),
+                            printable(right.get(&field).map_or("(missing)", |v| v.as_str()))
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    println!(
+        "{} only in {}, {} only in {}, {} changed, {} identical",
+        diff.only_left.len(),
+        left_root,
+        diff.only_right.len(),
+        right_root,
+        diff.changed.len(),
+        diff.unchanged.len()
+    );
+
+    Ok(())
+}
+
+fn run_find(args: FindArgs) -> Result<()> {
+    let root = as_directory(&args.root_path);
+    let keys = if is_mock(&root) {
+        list_keys_recursive(
+            &mut VaultClient::new(MockClient::new()),
+            &root,
+            FromCache::No,
+        )?
+    } else {
+        list_keys_recursive(
+            &mut connect(args.profile, args.host, args.token)?,
+            &root,
+            FromCache::No,
+        )?
+    };
+
+    let matches = fuzzy_filter(&args.pattern, &keys);
+    for (key, _) in matches.iter().take(args.limit.unwrap_or(usize::MAX)) {
+        println!("{}", printable(&format!("{}{}", root, key)));
+    }
+
+    match matches.len() {
+        0 => Err(Error::Application(format!(
+            "no key below '{}' matches '{}'",
+            root, args.pattern
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn run_grep(args: GrepArgs) -> Result<()> {
+    let root = as_directory(&args.root_path);
+    let pattern = Regex::new(&args.pattern)?;
+
+    if is_mock(&root) {
+        grep_with(VaultClient::new(MockClient::new()), &root, &pattern, &args)
+    } else {
+        let client = connect(args.profile.clone(), args.host.clone(), args.token.clone())?;
+        grep_with(client, &root, &pattern, &args)
+    }
+}
+
+fn grep_with<H: HttpClient + Clone + Send>(
+    mut client: VaultClient<H>,
+    root: &str,
+    pattern: &Regex,
+    args: &GrepArgs,
+) -> Result<()> {
+    let concurrency = args.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+    let report = grep_tree(&mut client, root, pattern, concurrency, |_, _| ())?;
+
+    for m in &report.matches {
+        let names: Vec<&str> = m.fields.iter().map(|(name, _)| name.as_str()).collect();
+        println!(
+            "{} (matches: {})",
+            printable(&format!("{}{}", root, m.key)),
+            printable(&names.join(", "))
+        );
+        if args.show_values {
+            for (name, value) in &m.fields {
+                println!("    {}: {}", printable(name), printable(value));
+            }
+        }
+    }
+    for (path, err) in &report.failed {
+        eprintln!("could not read '{}': {}", path, err);
+    }
+
+    println!(
+        "{} of {} secrets match{}",
+        report.matches.len(),
+        report.scanned,
+        match report.failed.len() {
+            0 => String::new(),
+            n => format!(", {} could not be read", n),
+        }
+    );
+
+    // like grep, nothing found is a failure
+    match report.matches.len() {
+        0 => Err(Error::Application(format!(
+            "no secret below '{}' matches '{}'",
+            root, pattern
+        ))),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,505 @@
+use std::{collections::BTreeMap, fs::read_to_string, path::PathBuf, time::Duration};
+
+use crossterm::style::Color;
+use home::home_dir;
+use serde_derive::Deserialize;
+
+use crate::{
+    client::{KvVersion, Mount, UreqClient, VaultClient},
+    copy::DEFAULT_CLEAR_AFTER,
+    error::{Error, Result},
+    keymap::{KeyList, Keymap},
+    theme::{self, Theme, ThemeConfig},
+};
+
+/// Location of the config file in the home directory, `$VAULTWALKER_CONFIG` takes precedence.
+const CONFIG_PATH: &str = ".config/vaultwalker/config.toml";
+
+/// Content of `~/.config/vaultwalker/config.toml`.
+///
+/// ```toml
+/// default_profile = "staging"
+/// clipboard_timeout = 30
+/// theme = "mine"
+/// mouse = false
+///
+/// [profiles.prod]
+/// address = "https://vault.prod.example.com:8200"
+/// namespace = "team-a"
+/// root = "secret/my_company/"
+/// auth = { method = "token", token_file = "~/.vault-token-prod" }
+/// tls = { ca_cert = "~/certs/prod-ca.pem" }
+/// kv_versions = { "secret/" = 2 }
+/// production = true
+/// accent = "dark_magenta"
+///
+/// [themes.mine]
+/// base = "light"
+/// status = { fg = "white", bg = "dark_blue" }
+///
+/// [keys]
+/// delete = "D"
+/// quit = ["q", "ctrl+q"]
+/// ```
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub default_profile: Option<String>,
+    /// Seconds before a copied secret is removed from the clipboard, 0 to keep it
+    pub clipboard_timeout: Option<u64>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Keys of the actions replacing the default ones, see `Keymap`
+    #[serde(default)]
+    pub keys: BTreeMap<String, KeyList>,
+    /// Name of a built-in theme or of one in `themes`, `dark` by default
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub themes: BTreeMap<String, ThemeConfig>,
+    /// Capture the mouse to click and scroll in the listing, `true` by default
+    pub mouse: Option<bool>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub address: Option<String>,
+    pub auth: Option<Auth>,
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Root path opened when none is given on the command line
+    pub root: Option<String>,
+    /// Version of the KV mounts, for the tokens that cannot read the mount information
+    #[serde(default)]
+    pub kv_versions: BTreeMap<String, u8>,
+    /// Highlight the profile name to avoid mistakes
+    #[serde(default)]
+    pub production: bool,
+    /// Background color of the profile name
+    pub accent: Option<String>,
+}
+
+impl Profile {
+    pub fn accent(&self) -> Result<Option<Color>> {
+        self.accent.as_deref().map(theme::parse_color).transpose()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "method", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Auth {
+    Token {
+        token: Option<String>,
+        token_file: Option<String>,
+    },
+    Approle {
+        #[serde(default = "default_approle_mount")]
+        mount: String,
+        role_id: String,
+        secret_id: Option<String>,
+        secret_id_file: Option<String>,
+    },
+}
+
+fn default_approle_mount() -> String {
+    "approle".to_owned()
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file with the certificates to trust on top of the usual ones
+    pub ca_cert: Option<String>,
+    #[serde(default)]
+    pub skip_verify: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Credentials {
+    Token(String),
+    AppRole {
+        mount: String,
+        role_id: String,
+        secret_id: String,
+    },
+}
+
+/// Replace a leading `~/` with the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn read_secret_file(path: &str) -> Result<String> {
+    read_to_string(expand_home(path))
+        .map(|content| content.trim().to_owned())
+        .map_err(|err| Error::Application(format!("cannot read '{}': {}", path, err)))
+}
+
+impl Config {
+    /// Time before a copied secret is removed from the clipboard, `None` to keep it.
+    pub fn clipboard_clear_after(&self) -> Option<Duration> {
+        match self.clipboard_timeout.unwrap_or(DEFAULT_CLEAR_AFTER) {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        }
+    }
+
+    /// Whether the mouse clicks and scrolls in the listing instead of selecting text.
+    pub fn mouse(&self) -> bool {
+        self.mouse.unwrap_or(true)
+    }
+
+    /// Load the config file, a missing file is the same as an empty one.
+    pub fn load() -> Result<Self> {
+        let path = match std::env::var("VAULTWALKER_CONFIG") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => match home_dir() {
+                Some(home) => home.join(CONFIG_PATH),
+                None => return Ok(Self::default()),
+            },
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&read_to_string(&path)?).map_err(|err| {
+            Error::Application(format!("invalid config file '{}': {}", path.display(), err))
+        })
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self =
+            toml::from_str(content).map_err(|err| Error::Application(err.message().to_owned()))?;
+        // a conflict is reported at startup rather than when the key is pressed
+        config.keymap()?;
+        config.theme()?;
+        for (name, profile) in config.profiles.iter() {
+            profile
+                .accent()
+                .map_err(|err| Error::Application(format!("[profiles.{}]: {}", name, err)))?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn theme(&self) -> Result<Theme> {
+        Theme::named(self.theme.as_deref().unwrap_or("dark"), &self.themes)
+    }
+
+    pub fn keymap(&self) -> Result<Keymap> {
+        Keymap::new(&self.keys).map_err(|err| Error::Application(format!("[keys]: {}", err)))
+    }
+
+    /// The profile called `name`, or the default profile when no name is given.
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<(String, Profile)>> {
+        let name = match name.or(self.default_profile.as_deref()) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        match self.profiles.get(name) {
+            Some(profile) => Ok(Some((name.to_owned(), profile.clone()))),
+            None => Err(Error::Application(format!(
+                "unknown profile '{}', the config file defines: {}",
+                name,
+                match self.profiles.len() {
+                    0 => "no profile".to_owned(),
+                    _ => self.profiles.keys().cloned().collect::<Vec<_>>().join(", "),
+                }
+            ))),
+        }
+    }
+}
+
+/// Everything needed to talk to a vault server, merged from the flags, the profile and the
+/// environment.
+#[derive(Clone, Debug)]
+pub struct Connection {
+    /// Name of the profile used, if any
+    pub profile: Option<String>,
+    pub address: String,
+    pub credentials: Credentials,
+    pub namespace: Option<String>,
+    pub tls: TlsConfig,
+    /// Root path of the profile, if it has one
+    pub root: Option<String>,
+    pub mounts: Vec<Mount>,
+}
+
+impl Connection {
+    /// Merge the flags with the profile and the environment.
+    ///
+    /// Each setting comes from the first of:
+    /// 1. the command line flags, `--host` and `--token`
+    /// 2. the profile selected with `--profile`, or the `default_profile` of the config file
+    /// 3. the environment, `VAULT_ADDR`, `VAULT_TOKEN`, `VAULT_NAMESPACE`, `VAULT_CACERT` and
+    ///    `VAULT_SKIP_VERIFY`
+    /// 4. the token in `~/.vault-token`
+    pub fn resolve(
+        config: &Config,
+        profile: Option<&str>,
+        host: Option<String>,
+        token: Option<String>,
+    ) -> Result<Self> {
+        Self::resolve_with(config, profile, host, token, |name| {
+            std::env::var(name).ok()
+        })
+    }
+
+    fn resolve_with(
+        config: &Config,
+        profile: Option<&str>,
+        host: Option<String>,
+        token: Option<String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let (name, profile) = match config.profile(profile)? {
+            Some((name, profile)) => (Some(name), profile),
+            None => (None, Profile::default()),
+        };
+
+        let address = host
+            .or(profile.address)
+            .or_else(|| env("VAULT_ADDR"))
+            .ok_or(Error::Application(
+                "please specify the vault server URL with -H option, in a profile or set the VAULT_ADDR environment variable".to_owned(),
+            ))?;
+
+        let credentials = match (token, profile.auth) {
+            (Some(token), _) => Credentials::Token(token),
+            (
+                None,
+                Some(Auth::Token {
+                    token: Some(token), ..
+                }),
+            ) => Credentials::Token(token),
+            (
+                None,
+                Some(Auth::Token {
+                    token_file: Some(path),
+                    ..
+                }),
+            ) => Credentials::Token(read_secret_file(&path)?),
+            (
+                None,
+                Some(Auth::Approle {
+                    mount,
+                    role_id,
+                    secret_id,
+                    secret_id_file,
+                }),
+            ) => Credentials::AppRole {
+                mount,
+                role_id,
+                secret_id: match (secret_id, secret_id_file) {
+                    (Some(secret_id), _) => secret_id,
+                    (None, Some(path)) => read_secret_file(&path)?,
+                    (None, None) => {
+                        return Err(Error::Application(
+                            "the AppRole auth needs a secret_id or a secret_id_file".to_owned(),
+                        ))
+                    }
+                },
+            },
+            (None, _) => Credentials::Token(
+                env("VAULT_TOKEN")
+                    .or_else(|| read_secret_file("~/.vault-token").ok())
+                    .ok_or(Error::Application(
+                        "cannot find ~/.vault-token file, please specify the token with -t option, in a profile or use the 'vault login' command to create it".to_owned(),
+                    ))?,
+            ),
+        };
+
+        let mounts = profile
+            .kv_versions
+            .iter()
+            .map(|(path, version)| {
+                let path = match path.ends_with('/') {
+                    true => path.clone(),
+                    false => format!("{}/", path),
+                };
+                match version {
+                    1 => Ok(Mount {
+                        path,
+                        version: KvVersion::V1,
+                    }),
+                    2 => Ok(Mount {
+                        path,
+                        version: KvVersion::V2,
+                    }),
+                    _ => Err(Error::Application(format!(
+                        "invalid KV version {} for the mount '{}', expected 1 or 2",
+                        version, path
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<Mount>>>()?;
+
+        let tls = TlsConfig {
+            ca_cert: profile.tls.ca_cert.or_else(|| env("VAULT_CACERT")),
+            skip_verify: profile.tls.skip_verify
+                || env("VAULT_SKIP_VERIFY").is_some_and(|v| v == "1" || v == "true"),
+        };
+
+        Ok(Self {
+            profile: name,
+            address,
+            credentials,
+            namespace: profile.namespace.or_else(|| env("VAULT_NAMESPACE")),
+            tls,
+            root: profile.root,
+            mounts,
+        })
+    }
+
+    /// Key of the bookmarks and recent paths in the state file.
+    pub fn state_key(&self) -> String {
+        self.profile.clone().unwrap_or(self.address.clone())
+    }
+
+    /// Build the client, logging in first for the auth methods that need it.
+    pub fn connect(&self) -> Result<VaultClient<UreqClient>> {
+        let token = match &self.credentials {
+            Credentials::Token(token) => token.as_str(),
+            Credentials::AppRole { .. } => "",
+        };
+        let ca_cert = self
+            .tls
+            .ca_cert
+            .as_ref()
+            .map(|path| expand_home(path).display().to_string());
+        let mut client = UreqClient::new(&self.address, token)
+            .with_namespace(self.namespace.clone())
+            .with_tls(ca_cert.as_deref(), self.tls.skip_verify)?;
+
+        if let Credentials::AppRole {
+            mount,
+            role_id,
+            secret_id,
+        } = &self.credentials
+        {
+            client.login_approle(mount, role_id, secret_id)?;
+        }
+
+        Ok(VaultClient::new(client).with_mounts(self.mounts.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        default_profile = "staging"
+
+        [profiles.staging]
+        address = "https://vault.staging:8200"
+        auth = { method = "token", token = "staging_token" }
+
+        [profiles.prod]
+        address = "https://vault.prod:8200"
+        namespace = "team-a"
+        root = "secret/my_company/"
+        auth = { method = "approle", role_id = "role", secret_id = "secret" }
+        tls = { skip_verify = true }
+        kv_versions = { "secret" = 2, "legacy/" = 1 }
+    "#;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "VAULT_ADDR" => Some("http://env:8200".to_owned()),
+            "VAULT_TOKEN" => Some("env_token".to_owned()),
+            "VAULT_NAMESPACE" => Some("env_namespace".to_owned()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(CONFIG).unwrap();
+        assert_eq!(config.profiles.len(), 2);
+        assert_eq!(config.default_profile.as_deref(), Some("staging"));
+        assert!(config.mouse());
+        assert!(!Config::parse("mouse = false").unwrap().mouse());
+
+        let err = Config::parse("[profiles.prod]\nadress = 'typo'").unwrap_err();
+        assert!(err.to_string().contains("unknown field `adress`"));
+        let err = Config::parse("[keys]\nquit = 'd'").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[keys]: the key 'd' is bound to both 'delete' and 'quit'"
+        );
+        let err = Config::parse("theme = 'solarized'").unwrap_err();
+        assert!(err.to_string().starts_with("unknown theme 'solarized'"));
+        let err = Config::parse(
+            "[profiles.prod]
+accent = 'pink'",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "[profiles.prod]: unknown color 'pink'");
+
+        let err = config.profile(Some("dev")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown profile 'dev', the config file defines: prod, staging"
+        );
+    }
+
+    #[test]
+    fn test_resolve_connection() {
+        let config = Config::parse(CONFIG).unwrap();
+
+        // the default profile is used, and the environment fills the gaps
+        let conn = Connection::resolve_with(&config, None, None, None, env).unwrap();
+        assert_eq!(conn.profile.as_deref(), Some("staging"));
+        assert_eq!(conn.address, "https://vault.staging:8200");
+        assert_eq!(
+            conn.credentials,
+            Credentials::Token("staging_token".to_owned())
+        );
+        assert_eq!(conn.namespace.as_deref(), Some("env_namespace"));
+
+        // the flags take precedence over the profile
+        let conn = Connection::resolve_with(
+            &config,
+            Some("prod"),
+            Some("http://flag:8200".to_owned()),
+            None,
+            env,
+        )
+        .unwrap();
+        assert_eq!(conn.address, "http://flag:8200");
+        assert_eq!(conn.namespace.as_deref(), Some("team-a"));
+        assert_eq!(conn.root.as_deref(), Some("secret/my_company/"));
+        assert!(conn.tls.skip_verify);
+        assert_eq!(
+            conn.credentials,
+            Credentials::AppRole {
+                mount: "approle".to_owned(),
+                role_id: "role".to_owned(),
+                secret_id: "secret".to_owned(),
+            }
+        );
+        let mounts: Vec<(&str, KvVersion)> = conn
+            .mounts
+            .iter()
+            .map(|m| (m.path.as_str(), m.version))
+            .collect();
+        assert_eq!(
+            mounts,
+            vec![("legacy/", KvVersion::V1), ("secret/", KvVersion::V2)]
+        );
+
+        // without a profile, only the environment is used
+        let conn = Connection::resolve_with(&Config::default(), None, None, None, env).unwrap();
+        assert_eq!(conn.address, "http://env:8200");
+        assert_eq!(conn.credentials, Credentials::Token("env_token".to_owned()));
+
+        let err = Connection::resolve_with(&Config::default(), None, None, None, |_| None);
+        assert!(err.unwrap_err().to_string().contains("VAULT_ADDR"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    io::{stdout, Write},
+    time::{Duration, Instant},
+};
+
+use clipboard::{ClipboardContext, ClipboardProvider};
+
+use crate::error::{Error, Result};
+
+/// Seconds before a copied secret is removed from the clipboard, unless set in the config file.
+pub const DEFAULT_CLEAR_AFTER: u64 = 30;
+
+enum Backend {
+    /// The clipboard of X11, Wayland, macOS or Windows
+    System(ClipboardContext),
+    /// OSC 52 escape sequence, the terminal sets its own clipboard, also through SSH and tmux
+    Terminal,
+    /// Clipboard kept in memory, to exercise the clearing
+    #[cfg(test)]
+    Memory(String),
+}
+
+/// A copied secret waiting to be removed from the clipboard.
+struct PendingClear {
+    at: Instant,
+    copied: String,
+    previous: Option<String>,
+}
+
+pub struct Clipboard {
+    backend: Backend,
+    clear_after: Option<Duration>,
+    pending: Option<PendingClear>,
+}
+
+impl Clipboard {
+    /// Use the system clipboard, or the terminal when there is none, e.g. in an SSH session.
+    pub fn new() -> Self {
+        Self {
+            backend: match ClipboardProvider::new() {
+                Ok(context) => Backend::System(context),
+                Err(_) => Backend::Terminal,
+            },
+            clear_after: Some(Duration::from_secs(DEFAULT_CLEAR_AFTER)),
+            pending: None,
+        }
+    }
+
+    /// Time before a copied secret is removed, `None` to keep it.
+    pub fn set_clear_after(&mut self, clear_after: Option<Duration>) {
+        self.clear_after = clear_after;
+    }
+
+    pub fn copy(&mut self, text: String) -> Result<()> {
+        match &mut self.backend {
+            Backend::System(context) => context
+                .set_contents(text)
+                .map_err(|err| Error::Application(format!("cannot copy: {}", err))),
+            Backend::Terminal => osc52(&text),
+            #[cfg(test)]
+            Backend::Memory(contents) => {
+                *contents = text;
+                Ok(())
+            }
+        }
+    }
+
+    /// What is in the clipboard, `None` when it cannot be read.
+    fn contents(&mut self) -> Option<String> {
+        match &mut self.backend {
+            Backend::System(context) => context.get_contents().ok(),
+            Backend::Terminal => None,
+            #[cfg(test)]
+            Backend::Memory(contents) => Some(contents.clone()),
+        }
+    }
+
+    /// Copy a secret, what was in the clipboard before is put back after `clear_after`.
+    ///
+    /// Returns the time before the secret is removed.
+    pub fn copy_secret(&mut self, secret: String) -> Result<Option<Duration>> {
+        let current = self.contents();
+        // copying a secret over another one restores what was there before both
+        let previous = match self.pending.take() {
+            Some(pending) if current.is_none() || current.as_ref() == Some(&pending.copied) => {
+                pending.previous
+            }
+            _ => current,
+        };
+
+        self.copy(secret.clone())?;
+        if let Some(after) = self.clear_after {
+            self.pending = Some(PendingClear {
+                at: Instant::now() + after,
+                copied: secret,
+                previous,
+            });
+        }
+
+        Ok(self.clear_after)
+    }
+
+    /// When the copied secret must be removed, see `clear`.
+    pub fn clear_deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|p| p.at)
+    }
+
+    /// Put back what was in the clipboard before the secret, unless something else was copied.
+    ///
+    /// The clipboard of the terminal cannot be read, so it is emptied even if something was
+    /// copied from another program in the meantime.
+    pub fn clear(&mut self) -> Result<()> {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+
+        if let Backend::Terminal = self.backend {
+            return osc52("");
+        }
+        if self.contents().as_ref() != Some(&pending.copied) {
+            return Ok(());
+        }
+        self.copy(pending.previous.unwrap_or_default())
+    }
+}
+
+/// Ask the terminal to set its clipboard, tmux needs the sequence to be wrapped to forward it.
+fn osc52(text: &str) -> Result<()> {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    let sequence = match std::env::var_os("TMUX") {
+        Some(_) => format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b")),
+        None => sequence,
+    };
+    let mut stdout = stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize;
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(n >> (18 - 6 * i)) & 63] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"s3cr3t/value"), "czNjcjN0L3ZhbHVl");
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut clipboard = Clipboard {
+            backend: Backend::Memory("before".to_owned()),
+            clear_after: Some(Duration::ZERO),
+            pending: None,
+        };
+
+        // what was copied before the secret is put back
+        clipboard.copy_secret("secret".to_owned()).unwrap();
+        assert_eq!(clipboard.contents().as_deref(), Some("secret"));
+        clipboard.clear().unwrap();
+        assert_eq!(clipboard.contents().as_deref(), Some("before"));
+
+        // copying a secret over another one puts back what was there before both
+        clipboard.copy_secret("secret".to_owned()).unwrap();
+        clipboard.copy_secret("other secret".to_owned()).unwrap();
+        clipboard.clear().unwrap();
+        assert_eq!(clipboard.contents().as_deref(), Some("before"));
+
+        // something copied since the secret is left in place
+        clipboard.copy_secret("secret".to_owned()).unwrap();
+        clipboard.copy("copied since".to_owned()).unwrap();
+        clipboard.clear().unwrap();
+        assert_eq!(clipboard.contents().as_deref(), Some("copied since"));
+        assert!(clipboard.clear_deadline().is_none());
+    }
+}
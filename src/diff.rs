@@ -0,0 +1,138 @@
+use std::{collections::BTreeMap, thread};
+
+use crate::{
+    client::{FromCache, HttpClient, VaultClient, VaultSecret},
+    error::Result,
+    tree::list_keys_recursive,
+};
+
+/// Comparison of two subtrees key by key, the paths are relative to the compared roots.
+pub struct TreeDiff {
+    pub only_left: Vec<String>,
+    pub only_right: Vec<String>,
+    pub changed: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub left_values: BTreeMap<String, VaultSecret>,
+    pub right_values: BTreeMap<String, VaultSecret>,
+}
+
+/// Read every secret below `root`, indexed by their path relative to `root`.
+fn read_tree<H: HttpClient>(
+    client: &mut VaultClient<H>,
+    root: &str,
+) -> Result<BTreeMap<String, VaultSecret>> {
+    let mut values = BTreeMap::new();
+    for key in list_keys_recursive(client, root, FromCache::No)? {
+        let secret = client.get_secret(&format!("{}{}", root, key), FromCache::No)?;
+        values.insert(key, secret);
+    }
+
+    Ok(values)
+}
+
+impl TreeDiff {
+    /// Read both subtrees at the same time and compare them.
+    ///
+    /// The two sides can be on different servers, each client is used from its own thread.
+    pub fn compute<L: HttpClient + Send, R: HttpClient + Send>(
+        left: &mut VaultClient<L>,
+        left_root: &str,
+        right: &mut VaultClient<R>,
+        right_root: &str,
+    ) -> Result<Self> {
+        let (left_values, right_values) = thread::scope(|s| {
+            let left = s.spawn(|| read_tree(left, left_root));
+            let right = read_tree(right, right_root);
+
+            (left.join().expect("reading the tree panicked"), right)
+        });
+
+        Ok(Self::from_values(left_values?, right_values?))
+    }
+
+    /// Compare two subtrees of the same server, one after the other.
+    pub fn compute_local<H: HttpClient>(
+        client: &mut VaultClient<H>,
+        left_root: &str,
+        right_root: &str,
+    ) -> Result<Self> {
+        let left_values = read_tree(client, left_root)?;
+        let right_values = read_tree(client, right_root)?;
+
+        Ok(Self::from_values(left_values, right_values))
+    }
+
+    pub fn from_values(
+        left_values: BTreeMap<String, VaultSecret>,
+        right_values: BTreeMap<String, VaultSecret>,
+    ) -> Self {
+        let mut diff = Self {
+            only_left: vec![],
+            only_right: vec![],
+            changed: vec![],
+            unchanged: vec![],
+            left_values,
+            right_values,
+        };
+
+        for (key, value) in &diff.left_values {
+            match diff.right_values.get(key) {
+                None => diff.only_left.push(key.clone()),
+                Some(other) if other != value => diff.changed.push(key.clone()),
+                Some(_) => diff.unchanged.push(key.clone()),
+            }
+        }
+        diff.only_right = diff
+            .right_values
+            .keys()
+            .filter(|k| !diff.left_values.contains_key(*k))
+            .cloned()
+            .collect();
+
+        diff
+    }
+}
+
+/// Names of the fields that are missing on one side or have a different value.
+pub fn changed_fields(left: &VaultSecret, right: &VaultSecret) -> Vec<String> {
+    let left = left.fields();
+    let right = right.fields();
+    let mut fields: Vec<String> = left
+        .iter()
+        .filter(|(k, v)| right.get(*k) != Some(*v))
+        .map(|(k, _)| k.clone())
+        .chain(right.keys().filter(|k| !left.contains_key(*k)).cloned())
+        .collect();
+    fields.sort();
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::MockClient;
+
+    #[test]
+    fn test_tree_diff() {
+        let mut left = VaultClient::new(MockClient::new());
+        let mut right = VaultClient::new(MockClient::new());
+        left.write_secret("mock/app/same", "a").unwrap();
+        left.write_secret("mock/app/changed", "a").unwrap();
+        left.write_secret("mock/app/dir/left", "a").unwrap();
+        right.write_secret("mockv2/app/same", "a").unwrap();
+        right.write_secret("mockv2/app/changed", "b").unwrap();
+        right.write_secret("mockv2/app/dir/right", "a").unwrap();
+
+        let diff = TreeDiff::compute(&mut left, "mock/app/", &mut right, "mockv2/app/").unwrap();
+        assert_eq!(diff.only_left, vec!["dir/left"]);
+        // `mockv2/app/key` is part of the mock data
+        assert_eq!(diff.only_right, vec!["dir/right", "key"]);
+        assert_eq!(diff.changed, vec!["changed"]);
+        assert_eq!(diff.unchanged, vec!["same"]);
+        assert_eq!(
+            changed_fields(&diff.left_values["changed"], &diff.right_values["changed"]),
+            vec!["secret"]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::stdout;
+
+use crossterm::{
+    cursor::MoveToNextLine,
+    event::KeyCode,
+    execute,
+    style::{ContentStyle, Print},
+};
+
+use crate::{
+    diff::TreeDiff,
+    error::Result,
+    layout,
+    render::{self, escape, fit, shorten},
+    theme, MASK,
+};
+
+#[derive(PartialEq, Clone, Copy)]
+enum DiffStatus {
+    OnlyLeft,
+    OnlyRight,
+    Changed,
+    Identical,
+}
+
+struct DiffRow {
+    key: String,
+    status: DiffStatus,
+    left: Option<String>,
+    right: Option<String>,
+}
+
+/// Side by side comparison of two directories, shown in place of the listing.
+pub struct DiffView {
+    left_root: String,
+    right_root: String,
+    rows: Vec<DiffRow>,
+    selected: usize,
+    scroll: usize,
+    reveal: bool,
+    hide_identical: bool,
+}
+
+impl DiffView {
+    pub fn new(diff: &TreeDiff, left_root: &str, right_root: &str) -> Self {
+        let mut rows: Vec<DiffRow> = diff
+            .only_left
+            .iter()
+            .map(|k| (k, DiffStatus::OnlyLeft))
+            .chain(diff.only_right.iter().map(|k| (k, DiffStatus::OnlyRight)))
+            .chain(diff.changed.iter().map(|k| (k, DiffStatus::Changed)))
+            .chain(diff.unchanged.iter().map(|k| (k, DiffStatus::Identical)))
+            .map(|(key, status)| DiffRow {
+                key: key.clone(),
+                status,
+                left: diff.left_values.get(key).map(String::from),
+                right: diff.right_values.get(key).map(String::from),
+            })
+            .collect();
+        rows.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Self {
+            left_root: left_root.to_owned(),
+            right_root: right_root.to_owned(),
+            rows,
+            selected: 0,
+            scroll: 0,
+            reveal: false,
+            hide_identical: false,
+        }
+    }
+
+    /// Show the values from the start, e.g. with `--show-secrets`.
+    pub fn with_reveal(mut self, reveal: bool) -> Self {
+        self.reveal = reveal;
+        self
+    }
+
+    fn visible_rows(&self) -> Vec<&DiffRow> {
+        self.rows
+            .iter()
+            .filter(|r| !self.hide_identical || r.status != DiffStatus::Identical)
+            .collect()
+    }
+
+    pub fn controls(&self) -> &'static str {
+        "[R]eveal values    [H]ide identical keys    scroll with arrows or JK    [Q]uit the comparison"
+    }
+
+    /// Handle a key press, returns false when the view must be closed.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.visible_rows().len() => {
+                self.selected += 1
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('r') => self.reveal = !self.reveal,
+            KeyCode::Char('h') => {
+                self.hide_identical = !self.hide_identical;
+                self.selected = 0;
+                self.scroll = 0;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            _ => (),
+        }
+
+        true
+    }
+
+    fn value(&self, value: &Option<String>, max_len: usize) -> String {
+        let value = match (value, self.reveal) {
+            (None, _) => "".into(),
+            (Some(_), false) => MASK.into(),
+            (Some(v), true) => escape(v),
+        };
+
+        fit(&value, value.to_string(), max_len + 3)
+    }
+
+    pub fn print(&mut self, width: usize, height: usize) -> Result<()> {
+        let visible = height.saturating_sub(2);
+        self.scroll = layout::scroll(
+            self.selected,
+            self.scroll,
+            self.visible_rows().len(),
+            visible,
+        );
+
+        let rows = self.visible_rows();
+        let count = |status| self.rows.iter().filter(|r| r.status == status).count();
+        let header = format!(
+            "{} <> {} ({} only left, {} only right, {} changed, {} identical)",
+            self.left_root,
+            self.right_root,
+            count(DiffStatus::OnlyLeft),
+            count(DiffStatus::OnlyRight),
+            count(DiffStatus::Changed),
+            count(DiffStatus::Identical)
+        );
+        execute!(
+            stdout(),
+            Print(
+                theme::get()
+                    .title
+                    .apply(shorten(&escape(&header), width.saturating_sub(3)))
+            ),
+            MoveToNextLine(1)
+        )?;
+
+        // marker and separators take 7 columns, the rest is split between the key and the values
+        let key_width = rows
+            .iter()
+            .map(|r| render::width(&escape(&r.key)))
+            .max()
+            .unwrap_or(0)
+            .min(width / 3);
+        let value_width = (width.saturating_sub(key_width + 7) / 2)
+            .saturating_sub(3)
+            .max(3);
+
+        let theme = theme::get();
+        for (i, row) in rows.iter().enumerate().skip(self.scroll).take(visible) {
+            let marker = match row.status {
+                DiffStatus::OnlyLeft => theme.added.apply("<"),
+                DiffStatus::OnlyRight => theme.removed.apply(">"),
+                DiffStatus::Changed => theme.changed.apply("~"),
+                DiffStatus::Identical => ContentStyle::new().apply("="),
+            };
+            let key = escape(&row.key);
+            let key = fit(&key, key.to_string(), key_width.max(6));
+            let line = format!(
+                " {} {}| {}",
+                if i == self.selected {
+                    theme.selected.apply(key)
+                } else {
+                    ContentStyle::new().apply(key)
+                },
+                self.value(&row.left, value_width),
+                self.value(&row.right, value_width)
+            );
+
+            execute!(
+                stdout(),
+                Print(if i == self.selected { ">" } else { " " }),
+                Print(marker),
+                Print(line),
+                MoveToNextLine(1)
+            )?;
+        }
+
+        Ok(())
+    }
+}
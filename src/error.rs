@@ -18,6 +18,16 @@ quick_error! {
             display("serde_json Error: {}", err)
             source(err)
         }
+        /// Nothing exists at the requested path
+        NotFound(path: String) {
+            display("nothing found at '{}'", path)
+        }
+        /// Invalid regular expression
+        Regex(err: regex::Error) {
+            from()
+            display("invalid regex: {}", err)
+            source(err)
+        }
         /// Vault errors
         Vault(err: String) {
             display("vault error: {}", err)
@@ -0,0 +1,75 @@
+use std::{collections::BTreeMap, fs::OpenOptions, io::Write};
+
+use crate::{
+    client::{FromCache, HttpClient, VaultClient, VaultSecret},
+    config::expand_home,
+    error::{Error, Result},
+    tree::expand_entries,
+};
+
+/// Write the secrets of `entries`, keys and directories of `directory`, to `file` as a JSON
+/// object indexed by their full path. Returns the number of keys written.
+///
+/// Every secret is read before creating the file, which must not exist yet and is only
+/// readable by its owner.
+pub fn export_entries<H: HttpClient>(
+    client: &mut VaultClient<H>,
+    directory: &str,
+    entries: &[String],
+    file: &str,
+) -> Result<usize> {
+    let mut secrets = BTreeMap::new();
+    for key in expand_entries(client, directory, entries)? {
+        let secret: VaultSecret = client.get_secret(&key, FromCache::No)?;
+        secrets.insert(key, secret);
+    }
+    let json = serde_json::to_string_pretty(&secrets)?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut output = options
+        .open(expand_home(file))
+        .map_err(|err| Error::Application(format!("cannot create '{}': {}", file, err)))?;
+    output.write_all(json.as_bytes())?;
+    output.write_all(b"\n")?;
+
+    Ok(secrets.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::MockClient;
+
+    #[test]
+    fn test_export_entries() {
+        let mut client = VaultClient::new(MockClient::new());
+        let file =
+            std::env::temp_dir().join(format!("vaultwalker-export-{}.json", std::process::id()));
+        let file = file.to_str().unwrap();
+        let _ = std::fs::remove_file(file);
+
+        let entries = vec!["key2".to_owned(), "key9/".to_owned()];
+        assert_eq!(
+            export_entries(&mut client, "mock/", &entries, file).unwrap(),
+            3
+        );
+        let exported: BTreeMap<String, VaultSecret> =
+            serde_json::from_str(&std::fs::read_to_string(file).unwrap()).unwrap();
+        assert_eq!(
+            exported.keys().collect::<Vec<_>>(),
+            vec!["mock/key2", "mock/key9/nested", "mock/key9/other"]
+        );
+        assert_eq!(exported["mock/key2"].single_value(), Some("value"));
+
+        // an existing file is not overwritten
+        let err = export_entries(&mut client, "mock/", &entries, file).unwrap_err();
+        assert!(err.to_string().starts_with("cannot create"));
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,97 @@
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::error::Result;
+
+/// Filter of the current listing, either a substring or a glob when it contains `*` or `?`.
+///
+/// The matching ignores case unless the pattern contains an uppercase letter. A glob must match
+/// the whole name, e.g. `db_*` only keeps the names starting with `db_`.
+pub struct ListFilter {
+    pub pattern: String,
+    regex: Regex,
+}
+
+impl ListFilter {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let is_glob = pattern.contains(['*', '?']);
+        let mut regex = String::new();
+        if !pattern.chars().any(char::is_uppercase) {
+            regex.push_str("(?i)");
+        }
+        if is_glob {
+            regex.push('^');
+        }
+
+        // each literal part is captured to highlight it
+        let flush = |regex: &mut String, literal: &mut String| {
+            if !literal.is_empty() {
+                regex.push_str(&format!("({})", regex::escape(literal)));
+                literal.clear();
+            }
+        };
+        let mut literal = String::new();
+        for c in pattern.chars() {
+            match c {
+                '*' | '?' => {
+                    flush(&mut regex, &mut literal);
+                    regex.push_str(if c == '*' { ".*?" } else { "." });
+                }
+                _ => literal.push(c),
+            }
+        }
+        flush(&mut regex, &mut literal);
+        if is_glob {
+            regex.push('$');
+        }
+
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            regex: Regex::new(&regex)?,
+        })
+    }
+
+    /// The byte ranges of `name` matching the literal parts of the pattern, if `name` matches.
+    pub fn matches(&self, name: &str) -> Option<Vec<Range<usize>>> {
+        let captures = self.regex.captures(name)?;
+
+        Some(
+            captures
+                .iter()
+                .skip(1)
+                .flatten()
+                .map(|m| m.range())
+                .filter(|r| !r.is_empty())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_filter() {
+        let filter = ListFilter::new("db").unwrap();
+        let ranges = filter.matches("prod_DB_password").unwrap();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0], 5..7);
+        assert!(filter.matches("database").is_none());
+
+        // smart case
+        assert!(ListFilter::new("DB").unwrap().matches("db_host").is_none());
+
+        // an empty filter keeps everything
+        assert_eq!(ListFilter::new("").unwrap().matches("key"), Some(vec![]));
+
+        let filter = ListFilter::new("db_*_pass?").unwrap();
+        assert_eq!(filter.matches("db_prod_pass1"), Some(vec![0..3, 7..12]));
+        assert!(filter.matches("old_db_prod_pass1").is_none());
+        assert!(filter.matches("db_prod_pass12").is_none());
+
+        // the other regex characters are taken literally
+        assert!(ListFilter::new("a.b").unwrap().matches("axb").is_none());
+    }
+}
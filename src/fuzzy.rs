@@ -0,0 +1,159 @@
+/// Score of each matched character.
+const SCORE_MATCH: i64 = 16;
+/// Bonus when the character starts a path segment or a word, e.g. after `/` or `_`.
+const BONUS_BOUNDARY: i64 = 8;
+/// Bonus when the character directly follows the previous matched one.
+const BONUS_CONSECUTIVE: i64 = 4;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+#[derive(Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Index of the matched characters in the text, in chars
+    pub positions: Vec<usize>,
+}
+
+fn is_boundary(text: &[char], i: usize) -> bool {
+    i == 0 || matches!(text[i - 1], '/' | '_' | '-' | '.' | ' ')
+}
+
+/// Match `pattern` as a subsequence of `text`, the way fzf does.
+///
+/// The matching ignores case unless the pattern contains an uppercase letter. Among all the ways
+/// to match, the one with the best score is returned, favoring characters at the start of the
+/// path segments and consecutive characters.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(|c| c.is_uppercase());
+    let normalize = |c: char| match case_sensitive {
+        true => c,
+        false => c.to_lowercase().next().unwrap_or(c),
+    };
+    let pattern: Vec<char> = pattern.chars().map(normalize).collect();
+    let original: Vec<char> = text.chars().collect();
+    let text: Vec<char> = original.iter().map(|c| normalize(*c)).collect();
+
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: vec![],
+        });
+    }
+
+    // scores[i][j]: best score of the pattern up to `i` with `pattern[i]` matched on `text[j]`,
+    // and from[i][j] the position of the previous matched character
+    let (n, m) = (text.len(), pattern.len());
+    let mut scores = vec![vec![None; n]; m];
+    let mut from = vec![vec![0; n]; m];
+    for i in 0..m {
+        // best previous match leaving a gap before `j`, already penalized for that gap
+        let mut gap: Option<(i64, usize)> = None;
+        for j in 0..n {
+            if i > 0 && j >= 2 {
+                gap = gap.map(|(score, k)| (score - PENALTY_GAP_EXTENSION, k));
+                if let Some(score) = scores[i - 1][j - 2] {
+                    if gap.is_none_or(|(best, _)| score - PENALTY_GAP_START > best) {
+                        gap = Some((score - PENALTY_GAP_START, j - 2));
+                    }
+                }
+            }
+            if text[j] != pattern[i] {
+                continue;
+            }
+
+            let bonus = SCORE_MATCH
+                + match is_boundary(&original, j) {
+                    true => BONUS_BOUNDARY,
+                    false => 0,
+                };
+            if i == 0 {
+                scores[i][j] = Some(bonus);
+                continue;
+            }
+
+            let consecutive = match j {
+                0 => None,
+                _ => scores[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE, j - 1)),
+            };
+            let choice = match (consecutive, gap) {
+                (Some(c), Some(g)) if g.0 > c.0 => Some(g),
+                (c, g) => c.or(g),
+            };
+            if let Some((score, k)) = choice {
+                scores[i][j] = Some(score + bonus);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = scores[m - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .max_by_key(|(j, s)| (*s, std::cmp::Reverse(*j)))?;
+    let mut positions = vec![j];
+    for i in (1..m).rev() {
+        j = from[i][j];
+        positions.push(j);
+    }
+    positions.reverse();
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Match every candidate, the best matches first and the shortest first for equal scores.
+pub fn fuzzy_filter<'a>(pattern: &str, candidates: &'a [String]) -> Vec<(&'a String, FuzzyMatch)> {
+    let mut matches: Vec<(&String, FuzzyMatch)> = candidates
+        .iter()
+        .filter_map(|c| fuzzy_match(pattern, c).map(|m| (c, m)))
+        .collect();
+    matches.sort_by(|a, b| {
+        b.1.score
+            .cmp(&a.1.score)
+            .then(a.0.len().cmp(&b.0.len()))
+            .then(a.0.cmp(b.0))
+    });
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("xyz", "payments/stripe").is_none());
+        assert!(fuzzy_match("sp", "ps").is_none());
+        assert_eq!(fuzzy_match("", "abc").unwrap().score, 0);
+
+        let m = fuzzy_match("stwh", "payments/stripe/webhook_secret").unwrap();
+        assert_eq!(m.positions, vec![9, 10, 16, 19]);
+
+        // consecutive and boundary matches are preferred
+        let m = fuzzy_match("web", "a/wxeb/web").unwrap();
+        assert_eq!(m.positions, vec![7, 8, 9]);
+
+        // smart case
+        assert!(fuzzy_match("db", "DB_PASSWORD").is_some());
+        assert!(fuzzy_match("Db", "db_password").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_filter() {
+        let candidates = vec![
+            "secret/app/database".to_owned(),
+            "secret/payments/stripe/webhook_secret".to_owned(),
+            "secret/payments/stripe/key".to_owned(),
+            "secret/db".to_owned(),
+        ];
+
+        let matches = fuzzy_filter("stripewh", &candidates);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0, "secret/payments/stripe/webhook_secret");
+
+        let matches = fuzzy_filter("db", &candidates);
+        assert_eq!(matches[0].0, "secret/db");
+        assert_eq!(matches.len(), 2);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use regex::Regex;
+
+use crate::{
+    client::{FromCache, HttpClient, VaultClient, VaultSecret},
+    error::{Error, Result},
+    tree::list_keys_recursive,
+};
+
+/// Number of secrets read at the same time, enough to hide the latency without flooding vault.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+pub struct GrepMatch {
+    /// Path of the key, relative to the searched root
+    pub key: String,
+    /// Name and value of each matching field
+    pub fields: Vec<(String, String)>,
+}
+
+pub struct GrepReport {
+    pub matches: Vec<GrepMatch>,
+    pub scanned: usize,
+    pub failed: Vec<(String, Error)>,
+}
+
+/// Name and value of the fields of `secret` whose value matches `pattern`.
+pub fn matching_fields(secret: &VaultSecret, pattern: &Regex) -> Vec<(String, String)> {
+    secret
+        .fields()
+        .into_iter()
+        .filter(|(_, value)| pattern.is_match(value))
+        .collect()
+}
+
+/// Read every secret below `root` and keep the ones with a field matching `pattern`.
+///
+/// The secrets are read by `concurrency` threads, each with its own copy of the client.
+/// `progress` is called with the number of secrets read so far and the total.
+pub fn grep_tree<H: HttpClient + Clone + Send>(
+    client: &mut VaultClient<H>,
+    root: &str,
+    pattern: &Regex,
+    concurrency: usize,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<GrepReport> {
+    let keys = list_keys_recursive(client, root, FromCache::No)?;
+    let mut report = GrepReport {
+        matches: vec![],
+        scanned: 0,
+        failed: vec![],
+    };
+
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|s| {
+        for _ in 0..concurrency.clamp(1, keys.len().max(1)) {
+            let mut client = client.clone();
+            let sender = sender.clone();
+            let (keys, next) = (&keys, &next);
+            s.spawn(move || {
+                while let Some(key) = keys.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let path = format!("{}{}", root, key);
+                    let res = client.get_secret::<VaultSecret>(&path, FromCache::No);
+                    if sender.send((key, res)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for (key, res) in receiver {
+            report.scanned += 1;
+            progress(report.scanned, keys.len());
+            match res {
+                Ok(secret) => {
+                    let fields = matching_fields(&secret, pattern);
+                    if !fields.is_empty() {
+                        report.matches.push(GrepMatch {
+                            key: key.clone(),
+                            fields,
+                        });
+                    }
+                }
+                // soft deleted KV v2 keys are still listed
+                Err(Error::NotFound(_)) => (),
+                Err(err) => report.failed.push((format!("{}{}", root, key), err)),
+            }
+        }
+    });
+
+    report.matches.sort_by(|a, b| a.key.cmp(&b.key));
+    report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{DeleteMode, MockClient};
+
+    #[test]
+    fn test_grep_tree() {
+        let mut client = VaultClient::new(MockClient::new());
+        client
+            .write_secret("mock/db/main", "postgres://old-db.internal:5432")
+            .unwrap();
+        client.write_secret("mock/db/replica", "new-db").unwrap();
+        client
+            .write_secret("mockv2/app/db", "old-db.internal")
+            .unwrap();
+        client
+            .write_secret("mockv2/app/gone", "old-db.internal")
+            .unwrap();
+        client
+            .delete_secret_with("mockv2/app/gone", DeleteMode::Soft)
+            .unwrap();
+
+        let pattern = Regex::new(r"old-db\.internal").unwrap();
+        let mut calls = 0;
+        let report = grep_tree(&mut client, "mock/", &pattern, 4, |_, _| calls += 1).unwrap();
+        assert_eq!(report.scanned, 18);
+        assert_eq!(calls, 18);
+        assert_eq!(report.matches.len(), 1);
+        assert_eq!(report.matches[0].key, "db/main");
+        assert_eq!(report.matches[0].fields[0].0, "secret");
+        assert!(report.failed.is_empty());
+
+        let report = grep_tree(&mut client, "mockv2/", &pattern, 1, |_, _| ()).unwrap();
+        let keys: Vec<&str> = report.matches.iter().map(|m| m.key.as_str()).collect();
+        assert_eq!(keys, vec!["app/db"]);
+    }
+}
@@ -0,0 +1,587 @@
+use std::{collections::BTreeMap, fmt};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde_derive::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// What a key does in the browser.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    First,
+    Last,
+    Open,
+    Back,
+    CopyPath,
+    CopySecret,
+    Add,
+    Update,
+    Rename,
+    Move,
+    Copy,
+    Delete,
+    Undo,
+    ToggleSelection,
+    SelectAll,
+    InvertSelection,
+    SelectRange,
+    Export,
+    Compare,
+    Search,
+    Grep,
+    Filter,
+    Profiles,
+    Bookmark,
+    Jump,
+    Reveal,
+    Details,
+    ClearCache,
+    Help,
+    Quit,
+}
+
+/// Heading of the actions in the help.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Category {
+    Navigation,
+    Selection,
+    Secrets,
+    Editing,
+    Search,
+    Other,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Navigation,
+        Category::Selection,
+        Category::Secrets,
+        Category::Editing,
+        Category::Search,
+        Category::Other,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Navigation => "navigation",
+            Category::Selection => "selection",
+            Category::Secrets => "secrets",
+            Category::Editing => "editing",
+            Category::Search => "search",
+            Category::Other => "other",
+        }
+    }
+}
+
+impl Action {
+    pub const ALL: [Action; 36] = [
+        Action::Down,
+        Action::Up,
+        Action::PageDown,
+        Action::PageUp,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::First,
+        Action::Last,
+        Action::Open,
+        Action::Back,
+        Action::CopyPath,
+        Action::CopySecret,
+        Action::Add,
+        Action::Update,
+        Action::Rename,
+        Action::Move,
+        Action::Copy,
+        Action::Delete,
+        Action::Undo,
+        Action::ToggleSelection,
+        Action::SelectAll,
+        Action::InvertSelection,
+        Action::SelectRange,
+        Action::Export,
+        Action::Compare,
+        Action::Search,
+        Action::Grep,
+        Action::Filter,
+        Action::Profiles,
+        Action::Bookmark,
+        Action::Jump,
+        Action::Reveal,
+        Action::Details,
+        Action::ClearCache,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Name of the action in the `[keys]` table of the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::PageDown => "page-down",
+            Action::PageUp => "page-up",
+            Action::HalfPageDown => "half-page-down",
+            Action::HalfPageUp => "half-page-up",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Open => "open",
+            Action::Back => "back",
+            Action::CopyPath => "copy-path",
+            Action::CopySecret => "copy-secret",
+            Action::Add => "add",
+            Action::Update => "update",
+            Action::Rename => "rename",
+            Action::Move => "move",
+            Action::Copy => "copy",
+            Action::Delete => "delete",
+            Action::Undo => "undo",
+            Action::ToggleSelection => "toggle-selection",
+            Action::SelectAll => "select-all",
+            Action::InvertSelection => "invert-selection",
+            Action::SelectRange => "select-range",
+            Action::Export => "export",
+            Action::Compare => "compare",
+            Action::Search => "search",
+            Action::Grep => "grep",
+            Action::Filter => "filter",
+            Action::Profiles => "profiles",
+            Action::Bookmark => "bookmark",
+            Action::Jump => "jump",
+            Action::Reveal => "reveal",
+            Action::Details => "details",
+            Action::ClearCache => "clear-cache",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Down => "select the next entry",
+            Action::Up => "select the previous entry",
+            Action::PageDown => "move down a page",
+            Action::PageUp => "move up a page",
+            Action::HalfPageDown => "move down half a page",
+            Action::HalfPageUp => "move up half a page",
+            Action::First => "select the first entry",
+            Action::Last => "select the last entry",
+            Action::Open => "open the directory",
+            Action::Back => "go to the parent directory",
+            Action::CopyPath => "copy the path",
+            Action::CopySecret => "copy the secret",
+            Action::Add => "add a secret",
+            Action::Update => "update the secret",
+            Action::Rename => "rename the key",
+            Action::Move => "move the key or directory",
+            Action::Copy => "copy the key or directory",
+            Action::Delete => "delete the key or directory",
+            Action::Undo => "undo the last change of a key",
+            Action::ToggleSelection => "select or unselect the entry",
+            Action::SelectAll => "select every entry",
+            Action::InvertSelection => "invert the selection",
+            Action::SelectRange => "select up to the last selected entry",
+            Action::Export => "export the secrets to a JSON file",
+            Action::Compare => "compare the directory with another one",
+            Action::Search => "search a key",
+            Action::Grep => "find in the values",
+            Action::Filter => "filter the listing",
+            Action::Profiles => "switch profile",
+            Action::Bookmark => "bookmark the directory",
+            Action::Jump => "jump to a bookmark or a recent directory",
+            Action::Reveal => "reveal or hide the values",
+            Action::Details => "show the details of the key",
+            Action::ClearCache => "clear the cache",
+            Action::Help => "show the key bindings",
+            Action::Quit => "quit",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Down => &["down", "j"],
+            Action::Up => &["up", "k"],
+            Action::PageDown => &["pagedown"],
+            Action::PageUp => &["pageup"],
+            Action::HalfPageDown => &["ctrl+d"],
+            Action::HalfPageUp => &["ctrl+u"],
+            Action::First => &["home", "g"],
+            Action::Last => &["end", "G"],
+            Action::Open => &["right", "l", "enter"],
+            Action::Back => &["left", "h", "backspace"],
+            Action::CopyPath => &["p"],
+            Action::CopySecret => &["s"],
+            Action::Add => &["a"],
+            Action::Update => &["u"],
+            Action::Rename => &["r"],
+            Action::Move => &["m"],
+            Action::Copy => &["y"],
+            Action::Delete => &["d"],
+            Action::Undo => &["z"],
+            Action::ToggleSelection => &["space"],
+            Action::SelectAll => &["ctrl+a"],
+            Action::InvertSelection => &["*"],
+            Action::SelectRange => &["V"],
+            Action::Export => &["e"],
+            Action::Compare => &["="],
+            Action::Search => &["/"],
+            Action::Grep => &["f"],
+            Action::Filter => &["ctrl+f"],
+            Action::Profiles => &["ctrl+p"],
+            Action::Bookmark => &["b"],
+            Action::Jump => &["'"],
+            Action::Reveal => &["v"],
+            Action::Details => &["i"],
+            Action::ClearCache => &["c"],
+            Action::Help => &["?", "o"],
+            Action::Quit => &["q", "esc"],
+        }
+    }
+
+    /// Heading of the action in the help.
+    pub fn category(self) -> Category {
+        match self {
+            Action::Down
+            | Action::Up
+            | Action::PageDown
+            | Action::PageUp
+            | Action::HalfPageDown
+            | Action::HalfPageUp
+            | Action::First
+            | Action::Last
+            | Action::Open
+            | Action::Back
+            | Action::Jump
+            | Action::Bookmark => Category::Navigation,
+            Action::CopyPath
+            | Action::CopySecret
+            | Action::Reveal
+            | Action::Details
+            | Action::Export => Category::Secrets,
+            Action::ToggleSelection
+            | Action::SelectAll
+            | Action::InvertSelection
+            | Action::SelectRange => Category::Selection,
+            Action::Add
+            | Action::Update
+            | Action::Rename
+            | Action::Move
+            | Action::Copy
+            | Action::Delete
+            | Action::Undo => Category::Editing,
+            Action::Compare | Action::Search | Action::Grep | Action::Filter => Category::Search,
+            Action::Profiles | Action::ClearCache | Action::Help | Action::Quit => Category::Other,
+        }
+    }
+
+    /// The actions that still make sense once every key of the listing is gone.
+    pub fn allowed_when_empty(self) -> bool {
+        matches!(
+            self,
+            Action::Add
+                | Action::Bookmark
+                | Action::ClearCache
+                | Action::Grep
+                | Action::Help
+                | Action::Jump
+                | Action::Profiles
+                | Action::Quit
+                | Action::Undo
+        )
+    }
+}
+
+/// A key with its modifiers, written like `j`, `G`, `ctrl+d` or `pagedown` in the config file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("tab", KeyCode::Tab),
+];
+
+impl Key {
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = || Error::Application(format!("invalid key '{}'", text));
+        let mut parts: Vec<&str> = text.split('+').collect();
+        // `+` itself, alone or after modifiers
+        if text.ends_with("++") || text == "+" {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let (name, modifier_names) = parts.split_last().ok_or_else(invalid)?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in modifier_names {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ if name.eq_ignore_ascii_case("space") => KeyCode::Char(' '),
+            _ => match NAMED_KEYS
+                .iter()
+                .find(|(n, _)| name.eq_ignore_ascii_case(n))
+            {
+                Some((_, code)) => *code,
+                None => return Err(invalid()),
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// The case of a character already tells if shift is pressed, e.g. `shift+g` is `G`.
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Self {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            code => Self { code, modifiers },
+        }
+    }
+}
+
+impl From<&KeyEvent> for Key {
+    fn from(event: &KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            code => match NAMED_KEYS.iter().find(|(_, c)| *c == code) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{:?}", code),
+            },
+        }
+    }
+}
+
+/// Keys of an action in the config file, one or a list.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn keys(&self) -> Vec<&str> {
+        match self {
+            KeyList::One(key) => vec![key.as_str()],
+            KeyList::Many(keys) => keys.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// Keys that keep their meaning whatever the keymap, they can only be bound to `quit`.
+const RESERVED_KEYS: [&str; 2] = ["ctrl+c", "esc"];
+
+/// Keys of each action, the defaults with the ones of the config file.
+///
+/// The digits are kept for the counts, e.g. `5j`, `Esc` clears the filter before quitting and
+/// `Ctrl+C` always quits.
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new()).expect("the default keys have no conflict")
+    }
+}
+
+impl Keymap {
+    /// Replace the keys of the actions found in `overrides`, a key bound to two actions is an
+    /// error.
+    pub fn new(overrides: &BTreeMap<String, KeyList>) -> Result<Self> {
+        if let Some(name) = overrides
+            .keys()
+            .find(|name| !Action::ALL.iter().any(|a| a.name() == name.as_str()))
+        {
+            return Err(Error::Application(format!("unknown action '{}'", name)));
+        }
+
+        let mut bindings: Vec<(Key, Action)> = vec![];
+        for action in Action::ALL {
+            let keys = match overrides.get(action.name()) {
+                Some(keys) => keys.keys(),
+                None => action.default_keys().to_vec(),
+            };
+            for key in keys {
+                let key = Key::parse(key)?;
+                if matches!(key.code, KeyCode::Char('0'..='9')) && key.modifiers.is_empty() {
+                    return Err(Error::Application(format!(
+                        "the key '{}' of '{}' is kept for the counts",
+                        key,
+                        action.name()
+                    )));
+                }
+                if action != Action::Quit && RESERVED_KEYS.contains(&key.to_string().as_str()) {
+                    return Err(Error::Application(format!(
+                        "the key '{}' of '{}' is kept to clear the filter or the selection, and to quit",
+                        key,
+                        action.name()
+                    )));
+                }
+                if let Some((_, other)) = bindings.iter().find(|(k, _)| *k == key) {
+                    return Err(Error::Application(format!(
+                        "the key '{}' is bound to both '{}' and '{}'",
+                        key,
+                        other.name(),
+                        action.name()
+                    )));
+                }
+                bindings.push((key, action));
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = Key::from(event);
+        self.bindings
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Keys of an action as shown in the help, e.g. `down, j`.
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<String> = self.keys(action).iter().map(Key::to_string).collect();
+        keys.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(Key::parse("ctrl+d").unwrap().to_string(), "ctrl+d");
+        assert_eq!(Key::parse("shift+g").unwrap(), Key::parse("G").unwrap());
+        assert_eq!(Key::parse("PageDown").unwrap().to_string(), "pagedown");
+        assert_eq!(Key::parse("alt++").unwrap().to_string(), "alt++");
+        assert_eq!(Key::parse("space").unwrap().to_string(), "space");
+        assert!(Key::parse("hyper+x").is_err());
+        assert!(Key::parse("nope").is_err());
+
+        // the terminals send shift with the uppercase letters
+        assert_eq!(
+            Key::from(&event(KeyCode::Char('G'), KeyModifiers::SHIFT)),
+            Key::parse("G").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_keymap() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(&event(KeyCode::Char('d'), KeyModifiers::NONE)),
+            Some(Action::Delete)
+        );
+        assert_eq!(
+            keymap.action(&event(KeyCode::Char('d'), KeyModifiers::CONTROL)),
+            Some(Action::HalfPageDown)
+        );
+        assert_eq!(keymap.describe(Action::Quit), "q, esc");
+
+        let overrides = |toml: &str| -> BTreeMap<String, KeyList> { toml::from_str(toml).unwrap() };
+        let keymap = Keymap::new(&overrides("delete = 'D'\nquit = ['x', 'ctrl+q']")).unwrap();
+        assert_eq!(
+            keymap.action(&event(KeyCode::Char('d'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            keymap.action(&event(KeyCode::Char('D'), KeyModifiers::SHIFT)),
+            Some(Action::Delete)
+        );
+        assert_eq!(keymap.describe(Action::Quit), "x, ctrl+q");
+        assert!(Keymap::new(&overrides("details = []"))
+            .unwrap()
+            .keys(Action::Details)
+            .is_empty());
+
+        assert_eq!(
+            Keymap::new(&overrides("quit = 'd'"))
+                .unwrap_err()
+                .to_string(),
+            "the key 'd' is bound to both 'delete' and 'quit'"
+        );
+        assert_eq!(
+            Keymap::new(&overrides("remove = 'd'"))
+                .unwrap_err()
+                .to_string(),
+            "unknown action 'remove'"
+        );
+        assert!(Keymap::new(&overrides("down = '5'")).is_err());
+        assert_eq!(
+            Keymap::new(&overrides("back = ['h', 'esc']"))
+                .unwrap_err()
+                .to_string(),
+            "the key 'esc' of 'back' is kept to clear the filter or the selection, and to quit"
+        );
+        assert!(Keymap::new(&overrides("filter = 'ctrl+c'")).is_err());
+        assert!(Keymap::new(&overrides("quit = ['q', 'ctrl+c']")).is_ok());
+
+        // `?` and `o` both show the help
+        assert_eq!(Keymap::default().describe(Action::Help), "?, o");
+    }
+}
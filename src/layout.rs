@@ -0,0 +1,205 @@
+/// Terminals narrower than this only show the current listing.
+pub const MIN_COLUMNS_WIDTH: usize = 90;
+/// Terminals shorter than this only show the current listing, the columns need a header.
+pub const MIN_COLUMNS_HEIGHT: usize = 4;
+/// Terminals shorter than this keep every row for the listing instead of the status bar.
+pub const MIN_STATUS_HEIGHT: usize = 6;
+/// Between two panes.
+pub const SEPARATOR: &str = " │ ";
+
+/// Size of the panes of the browser for a terminal size.
+#[derive(Debug, PartialEq)]
+pub struct Layout {
+    pub width: usize,
+    pub height: usize,
+    /// Width of the parent, current and preview panes, `None` to show the listing alone
+    pub columns: Option<[usize; 3]>,
+    /// Show the status bar above the messages
+    pub status_bar: bool,
+}
+
+impl Layout {
+    pub fn new(width: usize, height: usize) -> Self {
+        let columns = match width >= MIN_COLUMNS_WIDTH && height >= MIN_COLUMNS_HEIGHT {
+            true => {
+                let available = width - 2 * SEPARATOR.chars().count();
+                let parent = available / 5;
+                let current = available * 2 / 5;
+                Some([parent, current, available - parent - current])
+            }
+            false => None,
+        };
+
+        Self {
+            width,
+            height,
+            columns,
+            status_bar: height >= MIN_STATUS_HEIGHT,
+        }
+    }
+
+    /// Number of entries shown at once, the columns have the path above them and every layout
+    /// keeps the last line for the messages.
+    pub fn list_rows(&self) -> usize {
+        let reserved = 1 + self.columns.is_some() as usize + self.status_bar as usize;
+        self.height.saturating_sub(reserved)
+    }
+
+    /// Row of the status bar, just above the messages.
+    pub fn status_row(&self) -> Option<usize> {
+        self.status_bar.then(|| self.height - 2)
+    }
+
+    /// Column where the current pane starts.
+    pub fn current_start(&self) -> usize {
+        match self.columns {
+            Some([parent, ..]) => parent + SEPARATOR.chars().count(),
+            None => 0,
+        }
+    }
+
+    /// Row of the listing under a click, counted from the first entry shown, `None` outside of
+    /// the current pane.
+    pub fn list_row_at(&self, column: usize, row: usize) -> Option<usize> {
+        let row = match self.columns {
+            Some([_, current, _]) => {
+                let start = self.current_start();
+                if column < start || column >= start + current {
+                    return None;
+                }
+                row.checked_sub(1)?
+            }
+            None => row,
+        };
+
+        (row < self.list_rows()).then_some(row)
+    }
+
+    /// First entry of the listing to show, see `scroll`.
+    pub fn scroll(&self, selected: usize, scroll: usize, total: usize) -> usize {
+        self::scroll(selected, scroll, total, self.list_rows())
+    }
+}
+
+/// First entry to show on `rows` rows so that `selected` is visible, with one entry around it
+/// when there is room for it, and without blank rows after the last of the `total` entries.
+pub fn scroll(selected: usize, scroll: usize, total: usize, rows: usize) -> usize {
+    if rows == 0 {
+        return selected;
+    }
+    let margin = match rows {
+        0..=2 => 0,
+        _ => 1,
+    };
+
+    let mut scroll = scroll;
+    if selected < scroll + margin {
+        scroll = selected.saturating_sub(margin);
+    }
+    if selected + margin >= scroll + rows {
+        scroll = selected + margin + 1 - rows;
+    }
+
+    max_scroll(total, rows).min(scroll).min(selected)
+}
+
+/// Last first entry to show on `rows` rows, so that the rows stay filled.
+pub fn max_scroll(total: usize, rows: usize) -> usize {
+    total.saturating_sub(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let layout = Layout::new(120, 40);
+        assert_eq!(layout.columns, Some([22, 45, 47]));
+        assert_eq!(layout.list_rows(), 37);
+        assert_eq!(layout.status_row(), Some(38));
+        assert_eq!(layout.current_start(), 25);
+
+        // a narrow terminal falls back to one column
+        let layout = Layout::new(80, 40);
+        assert_eq!(layout.columns, None);
+        assert_eq!(layout.list_rows(), 38);
+        assert_eq!(layout.current_start(), 0);
+
+        // and so does a short one
+        let layout = Layout::new(120, 3);
+        assert_eq!(layout.columns, None);
+        assert_eq!(layout.list_rows(), 2);
+        assert_eq!(layout.status_row(), None);
+        assert_eq!(Layout::new(120, 0).list_rows(), 0);
+    }
+
+    #[test]
+    fn test_list_row_at() {
+        let layout = Layout::new(120, 40);
+        assert_eq!(layout.list_row_at(30, 1), Some(0));
+        assert_eq!(layout.list_row_at(69, 37), Some(36));
+        // the header, the status bar, the last line and the other panes
+        assert_eq!(layout.list_row_at(30, 0), None);
+        assert_eq!(layout.list_row_at(30, 38), None);
+        assert_eq!(layout.list_row_at(30, 39), None);
+        assert_eq!(layout.list_row_at(10, 5), None);
+        assert_eq!(layout.list_row_at(70, 5), None);
+
+        let layout = Layout::new(80, 40);
+        assert_eq!(layout.list_row_at(0, 0), Some(0));
+        assert_eq!(layout.list_row_at(79, 37), Some(37));
+        assert_eq!(layout.list_row_at(0, 38), None);
+    }
+
+    #[test]
+    fn test_scroll() {
+        // 10 rows for the entries
+        let layout = Layout::new(80, 12);
+        assert_eq!(layout.scroll(0, 0, 5), 0);
+        assert_eq!(layout.scroll(4, 0, 5), 0);
+        assert_eq!(layout.scroll(8, 0, 30), 0);
+        // one entry stays visible after the selection
+        assert_eq!(layout.scroll(9, 0, 30), 1);
+        assert_eq!(layout.scroll(29, 0, 30), 20);
+        // and before it when going up
+        assert_eq!(layout.scroll(20, 20, 30), 19);
+        assert_eq!(layout.scroll(0, 19, 30), 0);
+        // the rows stay filled when the list gets shorter
+        assert_eq!(layout.scroll(3, 15, 12), 2);
+        assert_eq!(layout.scroll(11, 15, 12), 2);
+        // an entry being added after the last one
+        assert_eq!(layout.scroll(30, 0, 31), 21);
+
+        // tiny terminals
+        let layout = Layout::new(80, 3);
+        assert_eq!(layout.scroll(0, 0, 30), 0);
+        assert_eq!(layout.scroll(1, 0, 30), 0);
+        assert_eq!(layout.scroll(2, 0, 30), 1);
+        assert_eq!(layout.scroll(29, 5, 30), 28);
+        assert_eq!(layout.scroll(3, 10, 30), 3);
+        let layout = Layout::new(80, 2);
+        assert_eq!(layout.scroll(7, 0, 30), 7);
+        assert_eq!(layout.scroll(7, 9, 30), 7);
+        let layout = Layout::new(80, 1);
+        assert_eq!(layout.scroll(7, 3, 30), 7);
+        assert_eq!(layout.scroll(0, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_scroll_rows() {
+        // the views below a title and above the messages, e.g. the pickers
+        assert_eq!(scroll(0, 0, 3, 10), 0);
+        assert_eq!(scroll(12, 0, 30, 10), 4);
+        assert_eq!(scroll(12, 4, 30, 10), 4);
+        assert_eq!(scroll(3, 3, 30, 10), 2);
+        assert_eq!(scroll(29, 3, 30, 10), 20);
+        // a list shortened since, e.g. by a search query
+        assert_eq!(scroll(2, 20, 5, 10), 0);
+        // no room at all
+        assert_eq!(scroll(4, 0, 30, 0), 4);
+
+        assert_eq!(max_scroll(30, 10), 20);
+        assert_eq!(max_scroll(5, 10), 0);
+    }
+}
@@ -0,0 +1,306 @@
+use std::{io::stdout, time::Instant};
+
+use crossterm::{
+    cursor::{self, MoveTo},
+    event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::error::Result;
+
+/// What the caller handles while the line is edited.
+pub enum Interruption {
+    /// The terminal was resized, the screen must be drawn again
+    Resize,
+    /// The time given to `with_deadline` is reached, e.g. to clear the clipboard
+    Deadline,
+}
+
+pub enum EditAction {
+    Continue,
+    Submit,
+    Cancel,
+}
+
+/// Single line input typed in raw mode at the cursor position, e.g. after a prompt.
+///
+/// A line longer than the space left on the screen scrolls horizontally instead of wrapping.
+pub struct LineEditor {
+    buffer: Vec<char>,
+    /// Position of the cursor in `buffer`
+    cursor: usize,
+    /// First character shown
+    offset: usize,
+    /// Each character is shown as `*` until Ctrl+R is pressed
+    masked: bool,
+    deadline: Option<Instant>,
+}
+
+impl LineEditor {
+    /// Start with `initial` already typed, e.g. the current name of a key to rename.
+    pub fn new(initial: &str) -> Self {
+        let buffer: Vec<char> = initial.chars().collect();
+        Self {
+            cursor: buffer.len(),
+            buffer,
+            offset: 0,
+            masked: false,
+            deadline: None,
+        }
+    }
+
+    /// Hide the typed characters, e.g. for the value of a secret.
+    pub fn with_mask(mut self, masked: bool) -> Self {
+        self.masked = masked;
+        self
+    }
+
+    /// Interrupt the editing at `deadline`, if it is not over by then.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub fn line(&self) -> String {
+        self.buffer.iter().collect()
+    }
+
+    /// Edit the line until it is submitted with Enter, `None` when cancelled with Esc.
+    ///
+    /// `interrupt` draws the screen again when the terminal is resized, the line then continues
+    /// where it leaves the cursor, and handles the deadline.
+    pub fn read(
+        mut self,
+        interrupt: impl FnMut(Interruption) -> Result<()>,
+    ) -> Result<Option<String>> {
+        let origin = cursor::position()?;
+        execute!(stdout(), cursor::Show)?;
+        let res = self.edit(origin, interrupt);
+        execute!(stdout(), cursor::Hide)?;
+
+        Ok(match res? {
+            true => Some(self.line()),
+            false => None,
+        })
+    }
+
+    fn edit(
+        &mut self,
+        mut origin: (u16, u16),
+        mut interrupt: impl FnMut(Interruption) -> Result<()>,
+    ) -> Result<bool> {
+        loop {
+            self.draw(origin)?;
+            if let Some(deadline) = self.deadline {
+                if !poll(deadline.saturating_duration_since(Instant::now()))? {
+                    self.deadline = None;
+                    interrupt(Interruption::Deadline)?;
+                    continue;
+                }
+            }
+            let action = match read()? {
+                Event::Key(event) if event.kind != KeyEventKind::Release => self.handle_key(event),
+                Event::Paste(text) => {
+                    self.paste(&text);
+                    EditAction::Continue
+                }
+                Event::Resize(_, _) => {
+                    interrupt(Interruption::Resize)?;
+                    origin = cursor::position()?;
+                    self.offset = 0;
+                    EditAction::Continue
+                }
+                _ => EditAction::Continue,
+            };
+            match action {
+                EditAction::Continue => (),
+                EditAction::Submit => return Ok(true),
+                EditAction::Cancel => return Ok(false),
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, event: KeyEvent) -> EditAction {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = event.modifiers.contains(KeyModifiers::ALT);
+        match event.code {
+            KeyCode::Enter => return EditAction::Submit,
+            KeyCode::Esc => return EditAction::Cancel,
+            KeyCode::Char('c') if ctrl => return EditAction::Cancel,
+            KeyCode::Char('r') if ctrl => self.masked = !self.masked,
+            KeyCode::Left if ctrl || alt => self.cursor = self.word_start(),
+            KeyCode::Char('b') if alt => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl || alt => self.cursor = self.word_end(),
+            KeyCode::Char('f') if alt => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::End => self.cursor = self.buffer.len(),
+            KeyCode::Char('e') if ctrl => self.cursor = self.buffer.len(),
+            KeyCode::Backspace if alt || ctrl => self.delete_to(self.word_start()),
+            KeyCode::Char('w') if ctrl => self.delete_to(self.word_start()),
+            KeyCode::Backspace => self.delete_to(self.cursor.saturating_sub(1)),
+            KeyCode::Char('h') if ctrl => self.delete_to(self.cursor.saturating_sub(1)),
+            KeyCode::Delete if self.cursor < self.buffer.len() => {
+                self.buffer.remove(self.cursor);
+            }
+            KeyCode::Char('u') if ctrl => self.delete_to(0),
+            KeyCode::Char('k') if ctrl => self.buffer.truncate(self.cursor),
+            KeyCode::Char(c) if !ctrl && !alt => {
+                self.buffer.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            _ => (),
+        }
+
+        EditAction::Continue
+    }
+
+    /// Insert pasted text at the cursor, without the line break usually copied at its end.
+    pub fn paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let pasted: Vec<char> = text.trim_end_matches('\n').chars().collect();
+        let len = pasted.len();
+        self.buffer.splice(self.cursor..self.cursor, pasted);
+        self.cursor += len;
+    }
+
+    /// Remove the characters between `start` and the cursor.
+    fn delete_to(&mut self, start: usize) {
+        self.buffer.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    /// Start of the word before the cursor, the words are made of letters and digits.
+    fn word_start(&self) -> usize {
+        let mut i = self.cursor;
+        while i > 0 && !self.buffer[i - 1].is_alphanumeric() {
+            i -= 1;
+        }
+        while i > 0 && self.buffer[i - 1].is_alphanumeric() {
+            i -= 1;
+        }
+
+        i
+    }
+
+    fn word_end(&self) -> usize {
+        let mut i = self.cursor;
+        while i < self.buffer.len() && !self.buffer[i].is_alphanumeric() {
+            i += 1;
+        }
+        while i < self.buffer.len() && self.buffer[i].is_alphanumeric() {
+            i += 1;
+        }
+
+        i
+    }
+
+    /// Character shown for `c`, a pasted line break must not move the cursor to another line.
+    fn shown(&self, c: char) -> char {
+        match c {
+            _ if self.masked => '*',
+            '\n' => '↵',
+            c if c.is_control() => '?',
+            c => c,
+        }
+    }
+
+    fn char_width(&self, c: char) -> usize {
+        self.shown(c).width().unwrap_or(0)
+    }
+
+    fn draw(&mut self, origin: (u16, u16)) -> Result<()> {
+        let (width, _) = terminal::size()?;
+        let available = (width as usize)
+            .saturating_sub(origin.0 as usize + 1)
+            .max(1);
+        // the characters before the cursor must fit, a wide character takes two columns
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        }
+        let before = |editor: &Self| -> usize {
+            editor.buffer[editor.offset..editor.cursor]
+                .iter()
+                .map(|&c| editor.char_width(c))
+                .sum()
+        };
+        while self.offset < self.cursor && before(self) >= available {
+            self.offset += 1;
+        }
+
+        let mut visible = String::new();
+        let mut used = 0;
+        for &c in &self.buffer[self.offset..] {
+            used += self.char_width(c);
+            if used > available {
+                break;
+            }
+            visible.push(self.shown(c));
+        }
+        execute!(
+            stdout(),
+            MoveTo(origin.0, origin.1),
+            Clear(ClearType::UntilNewLine),
+            Print(visible),
+            MoveTo(origin.0 + before(self) as u16, origin.1)
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut LineEditor, code: KeyCode, modifiers: KeyModifiers) {
+        editor.handle_key(KeyEvent::new(code, modifiers));
+    }
+
+    fn type_text(editor: &mut LineEditor, text: &str) {
+        for c in text.chars() {
+            press(editor, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+    }
+
+    #[test]
+    fn test_line_editor() {
+        let mut editor = LineEditor::new("secret/app");
+        type_text(&mut editor, "/db");
+        assert_eq!(editor.line(), "secret/app/db");
+
+        press(&mut editor, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(editor.line(), "secret/app/");
+        press(&mut editor, KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(editor.line(), "secret/");
+
+        press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+        type_text(&mut editor, "old_");
+        press(&mut editor, KeyCode::Right, KeyModifiers::CONTROL);
+        press(&mut editor, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(editor.line(), "old_secret");
+
+        press(&mut editor, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(editor.line(), "");
+
+        // the line break copied with the text is dropped
+        editor.paste("a\r\nb\n");
+        press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+        type_text(&mut editor, "é");
+        assert_eq!(editor.line(), "a\nbé");
+        press(&mut editor, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(editor.masked);
+        assert!(matches!(
+            editor.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+            EditAction::Cancel
+        ));
+    }
+}
@@ -1,30 +1,76 @@
 mod client;
+mod commands;
+mod config;
+mod copy;
+mod diff;
+mod diff_view;
 mod error;
+mod export;
+mod filter;
+mod fuzzy;
+mod grep;
+mod keymap;
+mod layout;
+mod line_editor;
+mod pick;
+mod picker;
+mod render;
+mod search_view;
+mod state;
+mod sync;
+mod theme;
+mod tree;
+mod undo;
 
 use std::{
+    collections::{BTreeSet, HashMap},
     fmt,
-    fs::read_to_string,
-    io::{stdin, stdout},
+    io::stdout,
+    time::{Duration, Instant},
 };
 
-extern crate clipboard;
-
-use clipboard::{ClipboardContext, ClipboardProvider};
 use crossterm::{
     cursor::{self, MoveDown, MoveTo, MoveToNextLine},
-    event::{read, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        poll, read, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+        EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     execute,
-    style::{Print, StyledContent, Stylize},
+    style::{Print, StyledContent},
     terminal::{
         self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
         LeaveAlternateScreen,
     },
 };
 
-use client::{FromCache, HttpClient, MockClient, UreqClient, VaultSecret};
+use client::{
+    DeleteMode, FromCache, HttpClient, KvMetadata, KvVersion, MockClient, SecretDetails,
+    VaultSecret,
+};
+use commands::Command;
+use config::{Config, Connection};
+use copy::Clipboard;
+use diff::TreeDiff;
+use diff_view::DiffView;
 use error::{Error, Result};
+use export::export_entries;
+use filter::ListFilter;
+use grep::{grep_tree, DEFAULT_CONCURRENCY};
 use gumdrop::Options;
-use home::home_dir;
+use keymap::{Action, Category, Keymap};
+use layout::{Layout, SEPARATOR};
+use line_editor::{Interruption, LineEditor};
+use pick::{Pick, PickOutput};
+use picker::{Picker, PickerAction};
+use regex::Regex;
+use render::{escape, fit, shorten};
+use search_view::{SearchAction, SearchView};
+use state::{Location, SharedState, State};
+use theme::Theme;
+use tree::{ConflictPolicy, DeletePlan, TransferKind, TransferPlan, TreeIndex};
+use undo::{read_state, snapshot, Change, UndoStack};
+use zeroize::Zeroizing;
 
 use crate::client::VaultClient;
 
@@ -46,11 +92,33 @@ impl VaultEntry {
             is_dir,
         }
     }
+
+    /// Name as listed by vault, with a trailing `/` for a directory.
+    fn source(&self) -> String {
+        match self.is_dir {
+            true => format!("{}/", self.name),
+            false => self.name.clone(),
+        }
+    }
+}
+
+impl fmt::Display for VaultPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.entries
+            .iter()
+            .try_for_each(|entry| write!(f, "{}", entry))
+    }
 }
 
 impl fmt::Display for VaultEntry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}{}", self.name, if self.is_dir { "/" } else { "" })
+        // the names come from vault, they are escaped like every text shown
+        write!(
+            f,
+            "{}{}",
+            escape(&self.name),
+            if self.is_dir { "/" } else { "" }
+        )
     }
 }
 
@@ -60,15 +128,14 @@ struct VaultPath {
 
 impl VaultPath {
     fn join(&self) -> String {
-        self.entries
-            .iter()
-            .fold(String::new(), |acc, item| format!("{}{}", acc, item))
+        self.entries.iter().fold(String::new(), |acc, item| {
+            acc + &item.name + if item.is_dir { "/" } else { "" }
+        })
     }
 
+    /// Columns taken by the path on the screen.
     fn len(&self) -> usize {
-        self.entries
-            .iter()
-            .fold(0, |acc, item| acc + item.name.len() + 1)
+        render::width(&self.to_string())
     }
 
     fn decode(path: &str) -> VaultPath {
@@ -82,28 +149,121 @@ impl VaultPath {
     }
 }
 
-fn shorten_string(s: impl Into<String>, max_len: usize) -> String {
-    let s = s.into();
-    if max_len < s.len() {
-        format!("{}...", &s[0..max_len])
-    } else {
-        s
+/// Shown instead of the values of the secrets until they are revealed.
+const MASK: &str = "******";
+/// Two clicks on the same entry within this time open it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// How often the keys found by the search index are shown while it walks the tree.
+const INDEX_REFRESH: Duration = Duration::from_millis(200);
+
+/// Scrollable list shown in place of the directory listing, e.g. to preview the keys affected
+/// by an operation.
+struct ListView {
+    title: String,
+    /// Each line, with an optional note highlighted after it
+    lines: Vec<(String, Option<String>)>,
+    scroll: usize,
+}
+
+impl ListView {
+    fn new(title: String, lines: Vec<(String, Option<String>)>) -> Self {
+        Self {
+            title,
+            lines,
+            scroll: 0,
+        }
+    }
+
+    /// Scroll with the arrows or JK, returns true if the key was used.
+    fn handle_scroll(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => self.scroll += 1,
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+/// Version and last update of a secret, below its fields in the preview.
+fn preview_metadata(metadata: Result<Option<KvMetadata>>) -> Vec<String> {
+    match metadata {
+        Ok(Some(metadata)) => vec![
+            format!("version: {}", metadata.current_version),
+            format!("updated: {}", metadata.updated_time),
+        ],
+        Ok(None) => vec!["metadata: n/a (KV v1)".to_owned()],
+        Err(_) => vec!["metadata: cannot be read".to_owned()],
     }
 }
 
-fn read_line() -> Result<String> {
-    execute!(stdout(), cursor::Show)?;
-    disable_raw_mode()?;
-    let mut line = String::new();
-    stdin().read_line(&mut line)?;
-    enable_raw_mode()?;
-    execute!(stdout(), cursor::Hide)?;
+/// Lines of the detail view of a secret, the warnings of vault are highlighted.
+fn detail_lines(details: &SecretDetails, reveal: bool) -> Vec<(String, Option<String>)> {
+    let mut lines = vec![];
+    for (name, value) in details.secret.fields() {
+        let len = value.chars().count();
+        let value = match reveal {
+            true => value,
+            false => MASK.to_owned(),
+        };
+        lines.push((format!("{}: {} ({} characters)", name, value, len), None));
+    }
+
+    lines.push((String::new(), None));
+    match (details.metadata.as_ref(), details.version) {
+        (Some(metadata), _) => {
+            lines.push((format!("version: {}", metadata.current_version), None));
+            lines.push((format!("created: {}", metadata.created_time), None));
+            lines.push((format!("updated: {}", metadata.updated_time), None));
+            let max_versions = match metadata.max_versions {
+                0 => "unlimited".to_owned(),
+                n => n.to_string(),
+            };
+            lines.push((format!("max versions: {}", max_versions), None));
+            let delete_after = match metadata.delete_version_after.as_str() {
+                "0s" => "never",
+                after => after,
+            };
+            lines.push((format!("delete versions after: {}", delete_after), None));
+            for (name, value) in metadata.custom_metadata.iter().flatten() {
+                lines.push((format!("metadata {}: {}", name, value), None));
+            }
+        }
+        (None, KvVersion::V2) => lines.push(("metadata: cannot be read".to_owned(), None)),
+        (None, KvVersion::V1) => lines.push(("KV v1, no versions".to_owned(), None)),
+    }
 
-    while line.ends_with('\n') {
-        line.pop();
+    lines.push((String::new(), None));
+    lines.push((format!("request id: {}", details.request_id), None));
+    if let Some(lease_id) = details.lease_id.as_ref() {
+        let renewable = match details.renewable {
+            Some(true) => " (renewable)",
+            _ => "",
+        };
+        lines.push((format!("lease id: {}{}", lease_id, renewable), None));
+    }
+    for warning in &details.warnings {
+        lines.push(("warning:".to_owned(), Some(warning.clone())));
     }
 
-    Ok(line)
+    lines
+}
+
+/// Print a message on the last lines of the terminal.
+fn print_message(message: StyledContent<String>) -> Result<()> {
+    let (width, height) = terminal::size()?;
+    // the messages contain names and errors sent by vault
+    let message = StyledContent::new(*message.style(), escape(message.content()).into_owned());
+    let offset = 1 + render::width(message.content()) / (width as usize).max(1);
+    execute!(
+        stdout(),
+        MoveTo(0, height.saturating_sub(offset as u16)),
+        Clear(ClearType::CurrentLine),
+        Print(message),
+    )?;
+
+    Ok(())
 }
 
 #[derive(PartialEq, Copy, Clone)]
@@ -117,62 +277,328 @@ enum Mode {
     Navigation,
     TypingKey(EditMode),
     TypingSecret(EditMode),
+    TypingDestination(TransferKind),
+    ConfirmingTransfer,
     DeletingKey,
+    ConfirmingDirectoryDelete,
+    TypingDirectoryDelete(DeleteMode),
+    ShowingReport,
+    TypingComparedPath,
+    ShowingDiff,
+    Searching,
+    TypingGrepPattern,
+    Filtering,
+    PickingProfile,
+    TypingProfileRoot,
+    TypingBookmarkName,
+    PickingJump,
+    ShowingDetails,
+    TypingExportFile,
+    ConfirmingUndo,
+}
+
+enum JumpTarget {
+    Bookmark { name: String, path: String },
+    Recent(String),
+}
+
+/// When the token of the connection expires, looked up when connecting.
+#[derive(Debug, PartialEq)]
+enum TokenExpiry {
+    /// The token cannot look itself up
+    Unknown,
+    Never,
+    At(Instant),
+}
+
+impl TokenExpiry {
+    fn lookup<H: HttpClient>(client: &mut VaultClient<H>) -> Self {
+        match client.token_ttl() {
+            Ok(0) => TokenExpiry::Never,
+            Ok(ttl) => TokenExpiry::At(Instant::now() + Duration::from_secs(ttl)),
+            Err(_) => TokenExpiry::Unknown,
+        }
+    }
+
+    /// Time left at `now`, e.g. `2h05m`.
+    fn describe(&self, now: Instant) -> String {
+        let left = match self {
+            TokenExpiry::Unknown => return "?".to_owned(),
+            TokenExpiry::Never => return "no expiry".to_owned(),
+            TokenExpiry::At(at) => at.saturating_duration_since(now).as_secs(),
+        };
+        match left {
+            0 => "expired".to_owned(),
+            1..=59 => format!("{}s", left),
+            60..=3599 => format!("{}m{:02}s", left / 60, left % 60),
+            3600..=86399 => format!("{}h{:02}m", left / 3600, left % 3600 / 60),
+            _ => format!("{}d{:02}h", left / 86400, left % 86400 / 3600),
+        }
+    }
 }
 
+/// Build the client of a profile, or of the connection given at startup for `None`, the type
+/// of client stays the same for every profile.
+type Connector<H> = Box<dyn Fn(Option<&str>) -> Result<VaultClient<H>>>;
+
+/// Name of the connection given at startup in the profile picker, when it is not a profile.
+const STARTUP_PROFILE: &str = "(startup)";
+
 struct Vaultwalker<H: HttpClient> {
     client: VaultClient<H>,
-    clipboard: Option<ClipboardContext>,
+    clipboard: Clipboard,
     mode: Mode,
     quit_requested: bool,
     path: VaultPath,
     root_len: usize,
+    /// Listing of the current directory, narrowed by the filter if there is one
     current_list: Vec<VaultEntry>,
+    unfiltered_list: Vec<VaultEntry>,
+    filter: Option<ListFilter>,
+    /// The filter only applies to the directory where it was typed
+    filtered_directory: String,
     selected_item: usize,
     previous_selected_item: usize,
     scroll: usize,
-    selected_secret: Option<VaultSecret>,
-    displayed_message: Option<String>,
+    /// Wiped from memory when another entry is selected
+    selected_secret: Option<Zeroizing<VaultSecret>>,
+    /// Message on the last lines, printed again when the terminal is resized
+    displayed_message: Option<StyledContent<String>>,
     buffered_key: String,
+    pending_transfer: Option<TransferPlan>,
+    pending_delete: Option<DeletePlan>,
+    list_view: Option<ListView>,
+    diff_view: Option<DiffView>,
+    search_view: Option<SearchView>,
+    /// Every key below the root, kept between two searches
+    search_index: Option<TreeIndex>,
+    config: Config,
+    profile: Option<String>,
+    connect: Option<Connector<H>>,
+    /// Root path where each profile was left, `None` being the connection given at startup
+    saved_roots: HashMap<Option<String>, String>,
+    profile_picker: Option<Picker>,
+    /// Profiles listed in the picker, `None` being the connection given at startup
+    profile_choices: Vec<Option<String>>,
+    /// Profile chosen in the picker while its root is typed
+    pending_profile: Option<Option<String>>,
+    state: SharedState,
+    /// Key of the current profile in the state file
+    state_key: String,
+    /// Key of the connection given at startup when it is not a profile, it can be switched back to
+    startup_key: Option<String>,
+    jump_picker: Option<Picker>,
+    jump_targets: Vec<JumpTarget>,
+    /// Show the values of the secrets instead of a mask
+    reveal: bool,
+    pick: Option<Pick>,
+    /// Path or value chosen in the pick mode
+    picked: Option<String>,
+    /// Path and metadata lines of the key shown in the preview, read once per key even when it
+    /// fails
+    preview_metadata: Option<(String, Vec<String>)>,
+    /// Secret shown in the detail view
+    details: Option<SecretDetails>,
+    /// Number typed before a move to repeat it, e.g. `5j`
+    count: Option<usize>,
+    /// Time and entry of the last click, to detect a double click
+    last_click: Option<(Instant, usize)>,
+    keymap: Keymap,
+    token_expiry: TokenExpiry,
+    /// Entries of the current directory the bulk operations act on, see `VaultEntry::source`
+    selection: BTreeSet<String>,
+    /// The selection only applies to the directory where it was made
+    selection_directory: String,
+    /// Last entry toggled, where a range selection starts
+    selection_anchor: Option<String>,
+    /// Previous content of the keys written, deleted or renamed, for the current connection
+    undo: UndoStack,
 }
 
-impl<H: HttpClient> Vaultwalker<H> {
-    fn new(http_client: H, root: String) -> Result<Self> {
+impl<H: HttpClient + Clone + Send + 'static> Vaultwalker<H> {
+    fn new(client: VaultClient<H>, root: String) -> Result<Self> {
         let path = VaultPath::decode(&root);
         let vw = Self {
-            client: VaultClient::new(http_client),
-            clipboard: ClipboardProvider::new().ok(),
+            client,
+            clipboard: Clipboard::new(),
             mode: Mode::Navigation,
             quit_requested: false,
             root_len: path.entries.len(),
             path,
             current_list: vec![],
+            unfiltered_list: vec![],
+            filter: None,
+            filtered_directory: String::new(),
             selected_item: 0,
             previous_selected_item: 0,
             scroll: 0,
             selected_secret: None,
             displayed_message: None,
             buffered_key: String::new(),
+            pending_transfer: None,
+            pending_delete: None,
+            list_view: None,
+            diff_view: None,
+            search_view: None,
+            search_index: None,
+            config: Config::default(),
+            profile: None,
+            connect: None,
+            saved_roots: HashMap::new(),
+            profile_picker: None,
+            profile_choices: vec![],
+            pending_profile: None,
+            state: SharedState::default(),
+            state_key: String::new(),
+            startup_key: None,
+            jump_picker: None,
+            jump_targets: vec![],
+            reveal: false,
+            pick: None,
+            picked: None,
+            preview_metadata: None,
+            details: None,
+            count: None,
+            last_click: None,
+            keymap: Keymap::default(),
+            token_expiry: TokenExpiry::Unknown,
+            selection: BTreeSet::new(),
+            selection_directory: String::new(),
+            selection_anchor: None,
+            undo: UndoStack::default(),
         };
 
         Ok(vw)
     }
 
+    /// Allow switching to the profiles of `config`, `profile` being the one in use.
+    fn with_profiles(
+        mut self,
+        config: Config,
+        profile: Option<String>,
+        connect: Connector<H>,
+    ) -> Self {
+        self.clipboard
+            .set_clear_after(config.clipboard_clear_after());
+        // the keys were checked when the config file was loaded
+        self.keymap = config.keymap().unwrap_or_default();
+        self.config = config;
+        self.profile = profile;
+        self.connect = Some(connect);
+        self
+    }
+
+    /// Remember the bookmarks, the recent paths and the last location in `state`, under `key`.
+    fn with_state(mut self, state: SharedState, key: String) -> Self {
+        if self.profile.is_none() {
+            self.startup_key = Some(key.clone());
+        }
+        self.state = state;
+        self.state_key = key;
+        self
+    }
+
+    fn with_reveal(mut self, reveal: bool) -> Self {
+        self.reveal = reveal;
+        self
+    }
+
+    /// Quit when a key is chosen with Enter, to print its path or value.
+    fn with_pick(mut self, pick: Option<Pick>) -> Self {
+        self.pick = pick;
+        self
+    }
+
     fn setup(&mut self) -> Result<()> {
-        execute!(stdout(), cursor::Hide, EnterAlternateScreen)?;
+        execute!(
+            stdout(),
+            cursor::Hide,
+            EnterAlternateScreen,
+            EnableBracketedPaste
+        )?;
+        if self.config.mouse() {
+            execute!(stdout(), EnableMouseCapture)?;
+        }
         enable_raw_mode()?;
-        self.update_list(FromCache::No)?;
-        self.update_selected_secret(FromCache::No)?;
+        self.token_expiry = TokenExpiry::lookup(&mut self.client);
+        let last = self
+            .state
+            .lock()
+            .last_location(&self.state_key, &self.root());
+        match last {
+            Some(location) => self.restore_location(&location)?,
+            None => {
+                self.update_list(FromCache::No)?;
+                self.update_selected_secret(FromCache::No)?;
+            }
+        }
         self.print()?;
         self.print_controls()?;
 
         Ok(())
     }
 
+    fn root(&self) -> String {
+        VaultPath {
+            entries: self.path.entries[..self.root_len].to_vec(),
+        }
+        .join()
+    }
+
     fn get_selected_path(&self) -> String {
         self.path.join() + &self.current_list[self.selected_item].name
     }
 
+    /// Same as `get_selected_path` but with a trailing `/` for directories.
+    fn get_selected_source(&self) -> String {
+        self.path.join() + &self.current_list[self.selected_item].source()
+    }
+
+    /// Entries of the selection in the order of the listing.
+    ///
+    /// Like for select all, the entries hidden by the filter are left out.
+    fn selected_entries(&self) -> Vec<String> {
+        self.current_list
+            .iter()
+            .map(VaultEntry::source)
+            .filter(|source| self.selection.contains(source))
+            .collect()
+    }
+
+    fn toggle_selection(&mut self, index: usize) {
+        let source = self.current_list[index].source();
+        if !self.selection.remove(&source) {
+            self.selection.insert(source.clone());
+        }
+        self.selection_anchor = Some(source);
+    }
+
+    /// Select every entry from the last toggled one to `index`.
+    fn select_range(&mut self, index: usize) {
+        let anchor = self
+            .selection_anchor
+            .as_ref()
+            .and_then(|anchor| self.current_list.iter().position(|e| e.source() == *anchor))
+            .unwrap_or(index);
+        let (start, end) = (anchor.min(index), anchor.max(index));
+        for entry in self.current_list[start..=end].iter() {
+            self.selection.insert(entry.source());
+        }
+        self.selection_anchor = Some(self.current_list[index].source());
+    }
+
+    fn is_marked(&self, item: &VaultEntry) -> bool {
+        !self.selection.is_empty() && self.selection.contains(&item.source())
+    }
+
+    /// Shown before an entry of the selection.
+    fn marker(&self, item: &VaultEntry) -> char {
+        match self.is_marked(item) {
+            true => '+',
+            false => ' ',
+        }
+    }
+
     fn rename_key(&mut self, new_key: &str) -> Result<()> {
         // check if the key already exists
         self.update_list(FromCache::No)?;
@@ -183,30 +609,322 @@ impl<H: HttpClient> Vaultwalker<H> {
             )));
         }
 
-        // read the secret from the old key
-        self.update_selected_secret(FromCache::No)?;
-        let secret = self.selected_secret.as_ref().unwrap();
+        let entry = self.current_list[self.selected_item].clone();
+        let destination = self.path.join()
+            + &VaultEntry {
+                name: new_key.to_owned(),
+                is_dir: entry.is_dir,
+            }
+            .source();
+        let source = self.get_selected_source();
+        let plan = TransferPlan::new(&mut self.client, TransferKind::Move, &source, &destination)?;
 
-        // write the secret to the new key
-        let new_path = format!("{}{}", self.path.join(), new_key);
-        self.client
-            .write_secret(&new_path, &<&VaultSecret as Into<String>>::into(secret))?;
+        // renaming a directory can move a lot of keys, show them before doing anything
+        if entry.is_dir {
+            return self.preview_transfer(plan);
+        }
 
-        // delete the old key
-        self.client.delete_secret(&self.get_selected_path())?;
+        let change = Change::for_transfer(
+            &mut self.client,
+            format!("rename of '{}' to '{}'", source, destination),
+            &plan,
+            ConflictPolicy::Abort,
+        );
+        plan.execute(&mut self.client, ConflictPolicy::Abort)?;
+        let note = self.undo.record(change);
+        self.invalidate_search_index();
         self.set_selected_item(new_key, FromCache::No)?;
         self.print()?;
-        self.print_info("successfully renamed the key")
+        self.print_info(&format!("successfully renamed the key{}", note))
+    }
+
+    fn preview_transfer(&mut self, plan: TransferPlan) -> Result<()> {
+        self.list_view = Some(ListView::new(
+            format!(
+                "{} {}{} -> {} ({} keys, {} already exist)",
+                plan.kind.verb(),
+                match plan.entries {
+                    Some(entries) => format!("{} entries of ", entries),
+                    None => String::new(),
+                },
+                plan.source,
+                plan.destination,
+                plan.items.len(),
+                plan.conflicts()
+            ),
+            plan.items
+                .iter()
+                .map(|item| {
+                    (
+                        format!("{} -> {}", item.source, item.destination),
+                        item.conflict.then(|| "(exists)".to_owned()),
+                    )
+                })
+                .collect(),
+        ));
+        self.pending_transfer = Some(plan);
+        self.mode = Mode::ConfirmingTransfer;
+        self.print()?;
+        self.displayed_message = None;
+        self.print_transfer_prompt()
+    }
+
+    /// Navigate to the directory of the key at `path` and select it.
+    fn open_path(&mut self, path: &str) -> Result<()> {
+        let (directory, key) = path.rsplit_once('/').unwrap_or(("", path));
+        self.path = VaultPath::decode(&format!("{}/", directory));
+        self.previous_selected_item = 0;
+        self.scroll = 0;
+        self.set_selected_item(key, FromCache::Yes)?;
+        self.visit();
+
+        Ok(())
+    }
+
+    /// Open a directory, or the directory of a key, moving the root up if it is outside of it.
+    fn jump_to(&mut self, path: &str) -> Result<()> {
+        let target = VaultPath::decode(path);
+        let common = self.path.entries[..self.root_len]
+            .iter()
+            .zip(target.entries.iter())
+            .take_while(|(a, b)| a.name == b.name && b.is_dir)
+            .count();
+        self.root_len = self.root_len.min(common.max(1));
+
+        let (directory, key) = path.rsplit_once('/').unwrap_or(("", path));
+        self.path = VaultPath::decode(&format!("{}/", directory));
+        self.previous_selected_item = 0;
+        self.scroll = 0;
+        // the path may have been deleted since it was saved
+        if let Err(err) = self.set_selected_item(key, FromCache::Yes) {
+            self.climb_to_existing()?;
+            self.selected_item = 0;
+            self.update_selected_secret(FromCache::Yes)?;
+            return Err(err);
+        }
+        self.visit();
+
+        Ok(())
+    }
+
+    /// Quit with the path or the value of the selected key, to print it once the screen is left.
+    fn pick_selected(&mut self) -> Result<()> {
+        let path = self.get_selected_path();
+        self.picked = match self.pick {
+            Some(Pick::Path) => Some(path),
+            Some(Pick::Value) => match self.selected_secret.as_ref() {
+                Some(secret) => Some(String::from(&**secret)),
+                None => {
+                    return Err(Error::Application(format!(
+                        "cannot read the value of '{}'",
+                        path
+                    )))
+                }
+            },
+            None => return Ok(()),
+        };
+        self.quit_requested = true;
+
+        Ok(())
+    }
+
+    /// Select the entry at `index`, or the last one when there are fewer entries.
+    fn select(&mut self, index: usize) {
+        self.selected_item = index.min(self.current_list.len().saturating_sub(1));
+    }
+
+    /// Number of entries shown at once, to move by pages.
+    fn page_rows(&self) -> Result<usize> {
+        let (width, height) = terminal::size()?;
+
+        Ok(Layout::new(width as usize, height as usize)
+            .list_rows()
+            .max(1))
+    }
+
+    fn enter_selected(&mut self) -> Result<()> {
+        if self.path.entries.len() > 32 {
+            return Ok(());
+        }
+        let entry = self.current_list[self.selected_item].clone();
+        self.path.entries.push(entry);
+        self.update_list(FromCache::Yes)?;
+        self.select(self.selected_item);
+        self.scroll = 0;
+        self.visit();
+
+        Ok(())
+    }
+
+    fn go_up(&mut self) -> Result<()> {
+        if self.path.entries.len() < self.root_len + 1 {
+            return Ok(());
+        }
+        let last = self.path.entries.pop().unwrap();
+        self.set_selected_item(&last.name, FromCache::Yes)?;
+        self.scroll = 0;
+
+        Ok(())
+    }
+
+    fn open_details(&mut self) -> Result<()> {
+        self.details = Some(self.client.get_secret_details(&self.get_selected_path())?);
+        self.mode = Mode::ShowingDetails;
+        self.print_details()
+    }
+
+    /// Add the current directory to the recent paths.
+    fn visit(&mut self) {
+        let path = self.path.join();
+        self.state.lock().profile(&self.state_key).visit(&path);
+    }
+
+    /// Keep where the browser is in the state, to open it there at the next launch.
+    fn remember_location(&self) {
+        let location = Location {
+            path: self.path.join(),
+            selected: self
+                .current_list
+                .get(self.selected_item)
+                .map(|e| e.name.clone()),
+        };
+        self.state
+            .lock()
+            .profile(&self.state_key)
+            .last
+            .insert(self.root(), location);
+    }
+
+    /// Go back to a saved location, or to its closest directory that still exists.
+    fn restore_location(&mut self, location: &Location) -> Result<()> {
+        self.path = VaultPath::decode(&location.path);
+        self.selected_item = 0;
+        self.previous_selected_item = 0;
+        self.scroll = 0;
+
+        self.climb_to_existing()?;
+        if self.current_list.is_empty() {
+            self.selected_secret = None;
+            return Ok(());
+        }
+        let selected = location.selected.as_deref().unwrap_or_default();
+        self.set_selected_item(selected, FromCache::No)
+    }
+
+    /// The keys were modified, the search index must be built again.
+    fn invalidate_search_index(&mut self) {
+        self.search_index = None;
+    }
+
+    /// Reload the current directory after keys were removed, climbing up while it is empty.
+    fn climb_to_existing(&mut self) -> Result<()> {
+        loop {
+            match self.update_list(FromCache::No) {
+                Ok(()) if !self.unfiltered_list.is_empty() => {
+                    if self.current_list.is_empty() {
+                        self.filter = None;
+                        self.apply_filter();
+                    }
+                    return Ok(());
+                }
+                Ok(()) | Err(Error::NotFound(_)) => (),
+                Err(err) => return Err(err),
+            }
+
+            if self.path.entries.len() <= self.root_len {
+                self.current_list.clear();
+                return Ok(());
+            }
+            self.path.entries.pop();
+            self.scroll = 0;
+        }
     }
 
     fn update_list(&mut self, cache: FromCache) -> Result<()> {
         let path = self.path.join();
         let res = self.client.list_secrets(&path, cache)?;
-        self.current_list = res.keys.iter().map(|x| VaultEntry::decode(x)).collect();
+        if path != self.filtered_directory {
+            self.filter = None;
+        }
+        self.unfiltered_list = res.keys.iter().map(|x| VaultEntry::decode(x)).collect();
+        if path != self.selection_directory {
+            self.selection.clear();
+            self.selection_anchor = None;
+            self.selection_directory = path;
+        }
+        let sources: BTreeSet<String> = self
+            .unfiltered_list
+            .iter()
+            .map(VaultEntry::source)
+            .collect();
+        self.selection.retain(|source| sources.contains(source));
+        self.apply_filter();
 
         Ok(())
     }
 
+    fn apply_filter(&mut self) {
+        self.current_list = match self.filter.as_ref() {
+            Some(filter) => self
+                .unfiltered_list
+                .iter()
+                .filter(|entry| filter.matches(&entry.name).is_some())
+                .cloned()
+                .collect(),
+            None => self.unfiltered_list.clone(),
+        };
+    }
+
+    /// Apply the filter again after it was changed, keeping the selected key if it still matches.
+    fn refilter(&mut self) -> Result<()> {
+        let selected = self.current_list.get(self.selected_item).cloned();
+        self.apply_filter();
+        self.selected_item = selected
+            .and_then(|s| self.current_list.iter().position(|e| e.name == s.name))
+            .unwrap_or(0);
+        self.update_selected_secret(FromCache::Yes)
+    }
+
+    fn clear_filter(&mut self) -> Result<()> {
+        self.filter = None;
+        self.refilter()
+    }
+
+    /// The key, highlighting the parts matching the filter and the entries of the selection.
+    fn highlight(&self, item: &VaultEntry) -> String {
+        let line = self.highlight_filter(item);
+        match self.is_marked(item) {
+            true => theme::get().marked.apply(line).to_string(),
+            false => line,
+        }
+    }
+
+    fn highlight_filter(&self, item: &VaultEntry) -> String {
+        let ranges = match self.filter.as_ref().and_then(|f| f.matches(&item.name)) {
+            Some(ranges) if !ranges.is_empty() => ranges,
+            _ => return item.to_string(),
+        };
+
+        let mut line = String::new();
+        let mut start = 0;
+        for range in ranges {
+            line.push_str(&escape(&item.name[start..range.start]));
+            line.push_str(
+                &theme::get()
+                    .matched
+                    .apply(escape(&item.name[range.clone()]))
+                    .to_string(),
+            );
+            start = range.end;
+        }
+        line.push_str(&escape(&item.name[start..]));
+        if item.is_dir {
+            line.push('/');
+        }
+
+        line
+    }
+
     fn update_selected_secret(&mut self, cache: FromCache) -> Result<()> {
         // this is a security to avoid panic
         if self.selected_item >= self.current_list.len() {
@@ -222,18 +940,32 @@ impl<H: HttpClient> Vaultwalker<H> {
         }
 
         let res = self.client.get_secret(&self.get_selected_path(), cache)?;
-        self.selected_secret = Some(res);
+        self.selected_secret = Some(Zeroizing::new(res));
+        // the key may have been written since its metadata was read
+        if cache == FromCache::No {
+            self.preview_metadata = None;
+        }
 
         Ok(())
     }
 
     fn set_selected_item(&mut self, key: &str, cache: FromCache) -> Result<()> {
         self.update_list(cache)?;
+        // the key must be visible, e.g. after renaming it to a name that does not match
+        if !self.current_list.iter().any(|x| x.name == key)
+            && self.unfiltered_list.iter().any(|x| x.name == key)
+        {
+            self.filter = None;
+            self.apply_filter();
+        }
         self.selected_item = self
             .current_list
             .iter()
             .position(|x| x.name == key)
-            .unwrap_or(self.previous_selected_item);
+            .unwrap_or(
+                self.previous_selected_item
+                    .min(self.current_list.len().saturating_sub(1)),
+            );
         self.update_selected_secret(cache)
     }
 
@@ -250,17 +982,42 @@ impl<H: HttpClient> Vaultwalker<H> {
         max_width: usize,
     ) -> Result<String> {
         match self.mode {
-            Mode::Navigation | Mode::DeletingKey => {
-                let mut line = format!("> {}", item);
-
-                let remaining = if max_width < line.len() + 7 {
+            Mode::Navigation
+            | Mode::DeletingKey
+            | Mode::TypingDestination(_)
+            | Mode::ConfirmingTransfer
+            | Mode::ConfirmingDirectoryDelete
+            | Mode::TypingDirectoryDelete(_)
+            | Mode::ShowingReport
+            | Mode::TypingComparedPath
+            | Mode::ShowingDiff
+            | Mode::Searching
+            | Mode::TypingGrepPattern
+            | Mode::Filtering
+            | Mode::PickingProfile
+            | Mode::TypingProfileRoot
+            | Mode::TypingBookmarkName
+            | Mode::PickingJump
+            | Mode::ShowingDetails
+            | Mode::TypingExportFile
+            | Mode::ConfirmingUndo => {
+                let len = render::width(&format!("> {}", item));
+                let mut line = format!("> {}", self.highlight(item));
+
+                let remaining = if max_width < len + 7 {
                     0
                 } else {
-                    max_width - line.len() - 7
+                    max_width - len - 7
                 };
 
                 if let Some(secret) = self.selected_secret.as_ref() {
-                    line.push_str(&format!(" -> {}", shorten_string(secret, remaining).bold()));
+                    let value = match self.reveal {
+                        true => {
+                            shorten(&escape(&Zeroizing::new(String::from(&**secret))), remaining)
+                        }
+                        false => MASK.to_owned(),
+                    };
+                    line.push_str(&format!(" -> {}", theme::get().value.apply(value)));
                 }
 
                 Ok(line)
@@ -276,6 +1033,22 @@ impl<H: HttpClient> Vaultwalker<H> {
         execute!(stdout(), Clear(ClearType::All), cursor::Hide, MoveTo(0, 0))?;
         let (width, height) = terminal::size()?;
 
+        if self.list_view.is_some() {
+            return self.print_list_view(width as usize, height as usize);
+        }
+        if let Some(view) = self.diff_view.as_mut() {
+            return view.print(width as usize, height as usize);
+        }
+        if let Some(picker) = self.profile_picker.as_mut() {
+            return picker.print(width as usize, height as usize);
+        }
+        if let Some(picker) = self.jump_picker.as_mut() {
+            return picker.print(width as usize, height as usize);
+        }
+        if self.search_view.is_some() {
+            return self.print_search(width as usize, height as usize);
+        }
+
         let mut extended_item = Vec::new();
         match self.mode {
             Mode::TypingKey(EditMode::Insert) => extended_item.push(VaultEntry {
@@ -289,32 +1062,138 @@ impl<H: HttpClient> Vaultwalker<H> {
             _ => (),
         }
 
-        if self.selected_item <= self.scroll {
-            self.scroll = self.selected_item - if self.selected_item == 0 { 0 } else { 1 };
+        let layout = Layout::new(width as usize, height as usize);
+        self.scroll = layout.scroll(
+            self.selected_item,
+            self.scroll,
+            self.current_list.len() + extended_item.len(),
+        );
+
+        // the active profile is shown before the path, in red for production
+        let label = match self.profile.as_ref() {
+            Some(name) => {
+                let label = format!(" {} ", escape(name));
+                let len = render::width(&label);
+                let profile = self.config.profiles.get(name);
+                let style = theme::get().profile_label(
+                    profile.is_some_and(|p| p.production),
+                    // the accent was checked when the config file was loaded
+                    profile.and_then(|p| p.accent().ok().flatten()),
+                );
+                Some((len, style.apply(label).to_string()))
+            }
+            None => None,
+        };
+
+        self.print_status_bar(&layout)?;
+        match layout.columns {
+            Some(columns) => self.print_columns(&layout, columns, label, &extended_item),
+            None => self.print_single_column(&layout, label, &extended_item),
         }
+    }
 
-        if self.selected_item - self.scroll >= height as usize - 3 {
-            self.scroll = self.selected_item + 3
-                - height as usize
-                - if self.selected_item == self.current_list.len() + extended_item.len() - 1 {
-                    1
-                } else {
-                    0
-                };
+    /// Mode, profile, server, token lifetime, number of entries and cached responses, the
+    /// cursor goes back to the top.
+    fn print_status_bar(&self, layout: &Layout) -> Result<()> {
+        let row = match layout.status_row() {
+            Some(row) => row,
+            None => return Ok(()),
+        };
+
+        let mut parts = vec![self.mode_name().to_owned()];
+        if let Some(profile) = self.profile.as_ref() {
+            parts.push(escape(profile).into_owned());
+        }
+        parts.push(escape(self.client.address()).into_owned());
+        parts.push(format!(
+            "token {}",
+            self.token_expiry.describe(Instant::now())
+        ));
+        parts.push(match self.filter.is_some() {
+            true => format!(
+                "{} of {} entries",
+                self.current_list.len(),
+                self.unfiltered_list.len()
+            ),
+            false => format!("{} entries", self.current_list.len()),
+        });
+        if !self.selection.is_empty() {
+            let visible = self.selected_entries().len();
+            parts.push(match self.selection.len() - visible {
+                0 => format!("{} selected", visible),
+                hidden => format!("{} selected, {} hidden by the filter", visible, hidden),
+            });
         }
+        parts.push(match self.client.cached() {
+            0 => "cache empty".to_owned(),
+            cached => format!("{} cached", cached),
+        });
+
+        let status = format!(" {} ", parts.join(" │ "));
+        let status = render::fit(&status, status.clone(), layout.width);
+        execute!(
+            stdout(),
+            MoveTo(0, row as u16),
+            Print(theme::get().status.apply(status)),
+            MoveTo(0, 0)
+        )?;
+
+        Ok(())
+    }
 
+    fn mode_name(&self) -> &'static str {
+        match self.mode {
+            Mode::Navigation if self.pick.is_some() => "pick",
+            Mode::Navigation => "browse",
+            Mode::TypingKey(EditMode::Insert) | Mode::TypingSecret(EditMode::Insert) => "add",
+            Mode::TypingKey(EditMode::Update) => "rename",
+            Mode::TypingSecret(EditMode::Update) => "update",
+            Mode::TypingDestination(TransferKind::Move) => "move",
+            Mode::TypingDestination(TransferKind::Copy) => "copy",
+            Mode::ConfirmingTransfer => "transfer",
+            Mode::DeletingKey
+            | Mode::ConfirmingDirectoryDelete
+            | Mode::TypingDirectoryDelete(_) => "delete",
+            Mode::ShowingReport => "report",
+            Mode::TypingComparedPath | Mode::ShowingDiff => "compare",
+            Mode::Searching => "search",
+            Mode::TypingGrepPattern => "grep",
+            Mode::Filtering => "filter",
+            Mode::PickingProfile | Mode::TypingProfileRoot => "profiles",
+            Mode::TypingBookmarkName => "bookmark",
+            Mode::PickingJump => "jump",
+            Mode::ShowingDetails => "details",
+            Mode::TypingExportFile => "export",
+            Mode::ConfirmingUndo => "undo",
+        }
+    }
+
+    /// The path on the first line, followed by the entries of the listing.
+    fn print_single_column(
+        &mut self,
+        layout: &Layout,
+        label: Option<(usize, String)>,
+        extended_item: &[VaultEntry],
+    ) -> Result<()> {
         let mut len_selected = 0;
-        let prefix_len = self.path.len() + 1;
+        let prefix_len = self.path.len() + 1 + label.as_ref().map_or(0, |(len, _)| len + 1);
         for (i, item) in self
             .current_list
             .iter()
             .chain(extended_item.iter())
             .enumerate()
             .skip(self.scroll)
-            .take(height as usize - 1)
+            .take(layout.list_rows())
         {
             let mut line = if i == self.scroll {
-                format!("{} ", self.path.join().bold())
+                match label.as_ref() {
+                    Some((_, label)) => format!(
+                        "{} {} ",
+                        label,
+                        theme::get().path.apply(self.path.to_string())
+                    ),
+                    None => format!("{} ", theme::get().path.apply(self.path.to_string())),
+                }
             } else {
                 format!("{:prefix$}", "", prefix = prefix_len)
             };
@@ -322,270 +1201,1945 @@ impl<H: HttpClient> Vaultwalker<H> {
             if i == self.selected_item {
                 line.push_str(&self.selected_line_for_current_mode(
                     item,
-                    (width as i32 - line.len() as i32).max(3) as usize,
+                    layout.width.saturating_sub(render::width(&line)).max(3),
                 )?);
-                len_selected = line.len();
-                if i == self.scroll {
-                    // if the selected item is the first item, we need to remove the bold prefix
-                    len_selected -= 8;
-                }
+                len_selected = render::width(&line);
             } else {
-                line.push_str(&format!("  {}", item));
+                line.push_str(&format!("{} {}", self.marker(item), self.highlight(item)));
             }
 
             execute!(stdout(), Print(line), MoveToNextLine(1))?;
         }
 
-        match self.mode {
-            Mode::TypingKey(_) | Mode::TypingSecret(_) => {
-                execute!(
-                    stdout(),
-                    MoveTo(
-                        len_selected as u16,
-                        self.selected_item as u16 - self.scroll as u16
-                    )
-                )?;
-            }
-            _ => (),
-        };
+        if self.is_typing_in_list() {
+            execute!(
+                stdout(),
+                MoveTo(
+                    len_selected as u16,
+                    self.selected_item.saturating_sub(self.scroll) as u16
+                )
+            )?;
+        }
 
         Ok(())
     }
 
-    fn print_message_raw(&mut self, message: StyledContent<String>) -> Result<()> {
-        if self
-            .displayed_message
-            .as_ref()
-            .is_some_and(|m| m == message.content())
+    /// The parent directory, the listing and a preview of the selected entry side by side.
+    fn print_columns(
+        &mut self,
+        layout: &Layout,
+        [parent_width, current_width, preview_width]: [usize; 3],
+        label: Option<(usize, String)>,
+        extended_item: &[VaultEntry],
+    ) -> Result<()> {
+        let header = match label {
+            Some((_, label)) => format!(
+                "{} {}",
+                label,
+                theme::get().path.apply(self.path.to_string())
+            ),
+            None => theme::get().path.apply(self.path.to_string()).to_string(),
+        };
+        execute!(stdout(), Print(header), MoveToNextLine(1))?;
+
+        let rows = layout.list_rows();
+        let parent = self.parent_column(rows, parent_width);
+        let preview = self.preview_column(rows, preview_width);
+
+        let mut len_selected = 0;
+        let mut current = vec![];
+        for (i, item) in self
+            .current_list
+            .iter()
+            .chain(extended_item.iter())
+            .enumerate()
+            .skip(self.scroll)
+            .take(rows)
         {
-            return Ok(());
+            let line = if i != self.selected_item {
+                fit(
+                    &format!("{} {}", self.marker(item), item),
+                    format!("{} {}", self.marker(item), self.highlight(item)),
+                    current_width,
+                )
+            } else if self.is_typing_in_list() {
+                let line = self.selected_line_for_current_mode(item, current_width)?;
+                len_selected = render::width(&line);
+                fit(&line, line.clone(), current_width)
+            } else {
+                fit(
+                    &format!("> {}", item),
+                    format!("> {}", self.highlight(item)),
+                    current_width,
+                )
+            };
+            current.push(line);
         }
 
-        self.displayed_message = Some(message.content().clone());
-        let (width, height) = terminal::size()?;
-        let offset = 1 + message.content().len() / width as usize;
-        execute!(
-            stdout(),
-            MoveTo(0, (height - offset as u16).max(0)),
-            Clear(ClearType::CurrentLine),
-            Print(message),
-        )?;
+        let blank = |width| " ".repeat(width);
+        for row in 0..rows {
+            execute!(
+                stdout(),
+                Print(parent.get(row).cloned().unwrap_or(blank(parent_width))),
+                Print(theme::get().dim.apply(SEPARATOR)),
+                Print(current.get(row).cloned().unwrap_or(blank(current_width))),
+                Print(theme::get().dim.apply(SEPARATOR)),
+                Print(preview.get(row).cloned().unwrap_or_default()),
+                MoveToNextLine(1)
+            )?;
+        }
+
+        if self.is_typing_in_list() {
+            execute!(
+                stdout(),
+                MoveTo(
+                    (layout.current_start() + len_selected) as u16,
+                    1 + self.selected_item.saturating_sub(self.scroll) as u16
+                )
+            )?;
+        }
 
         Ok(())
     }
 
-    fn print_info(&mut self, message: &str) -> Result<()> {
-        self.print_message_raw(format!(" {} ", message).black().on_white())
+    /// A key is typed in place of the selected entry.
+    fn is_typing_in_list(&self) -> bool {
+        matches!(self.mode, Mode::TypingKey(_) | Mode::TypingSecret(_))
+    }
+
+    /// The entries of the parent directory, with the current directory highlighted.
+    fn parent_column(&mut self, rows: usize, width: usize) -> Vec<String> {
+        if self.path.entries.len() <= self.root_len {
+            return vec![];
+        }
+        let current = self.path.entries[self.path.entries.len() - 1].name.clone();
+        let parent = VaultPath {
+            entries: self.path.entries[..self.path.entries.len() - 1].to_vec(),
+        }
+        .join();
+        let entries = match self.client.list_secrets(&parent, FromCache::Yes) {
+            Ok(res) => res.keys,
+            Err(_) => return vec![],
+        };
+
+        let selected = entries
+            .iter()
+            .position(|k| k.trim_end_matches('/') == current)
+            .unwrap_or(0);
+        let start = selected
+            .saturating_sub(rows / 2)
+            .min(entries.len().saturating_sub(rows));
+        entries
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(rows)
+            .map(|(i, key)| (i, escape(key)))
+            .map(|(i, key)| match i == selected {
+                true => fit(
+                    &format!("> {}", key),
+                    format!("> {}", theme::get().selected.apply(key.as_ref())),
+                    width,
+                ),
+                false => fit(
+                    &format!("  {}", key),
+                    theme::get().dim.apply(format!("  {}", key)).to_string(),
+                    width,
+                ),
+            })
+            .collect()
+    }
+
+    /// The entries of the selected directory, or the fields of the selected secret.
+    fn preview_column(&mut self, rows: usize, width: usize) -> Vec<String> {
+        let dimmed = |text: &str| {
+            let text = escape(text);
+            vec![fit(&text, theme::get().dim.apply(&text).to_string(), width)]
+        };
+        let entry = match self.current_list.get(self.selected_item) {
+            Some(entry) if !self.is_typing_in_list() => entry.clone(),
+            _ => return vec![],
+        };
+
+        if entry.is_dir {
+            let path = self.get_selected_source();
+            return match self.client.list_secrets(&path, FromCache::Yes) {
+                Ok(res) if res.keys.is_empty() => dimmed("empty directory"),
+                Ok(res) => res
+                    .keys
+                    .iter()
+                    .take(rows)
+                    .map(|key| {
+                        let key = format!("  {}", escape(key));
+                        fit(&key, key.clone(), width)
+                    })
+                    .collect(),
+                Err(err) => dimmed(&err.to_string()),
+            };
+        }
+
+        let secret = match self.selected_secret.as_ref() {
+            Some(secret) => secret,
+            None => return dimmed("cannot read the secret"),
+        };
+        let mut lines: Vec<String> = secret
+            .fields()
+            .iter()
+            .map(|(name, value)| {
+                let name = escape(name);
+                let value = match self.reveal {
+                    true => escape(value),
+                    false => MASK.into(),
+                };
+                fit(
+                    &format!("{}: {}", name, value),
+                    format!("{}: {}", theme::get().title.apply(name.as_ref()), value),
+                    width,
+                )
+            })
+            .collect();
+
+        let path = self.get_selected_path();
+        if self.preview_metadata.as_ref().map(|(p, _)| p) != Some(&path) {
+            let metadata = preview_metadata(self.client.get_metadata(&path, FromCache::Yes));
+            self.preview_metadata = Some((path, metadata));
+        }
+        if let Some((_, metadata)) = self.preview_metadata.as_ref() {
+            lines.push(String::new());
+            lines.extend(metadata.iter().flat_map(|line| dimmed(line)));
+        }
+        lines.truncate(rows);
+
+        lines
+    }
+
+    fn print_search(&mut self, width: usize, height: usize) -> Result<()> {
+        let root = self.root();
+        let status = match self.search_index.as_ref() {
+            Some(index) => format!(
+                "{} keys{}{}",
+                index.keys.len(),
+                if index.is_done() { "" } else { ", indexing..." },
+                match index.errors {
+                    0 => String::new(),
+                    n => format!(", {} directories could not be listed", n),
+                }
+            ),
+            None => String::new(),
+        };
+
+        match self.search_view.as_mut() {
+            Some(view) => view.print(&root, &status, width, height),
+            None => Ok(()),
+        }
+    }
+
+    fn print_list_view(&mut self, width: usize, height: usize) -> Result<()> {
+        let view = match self.list_view.as_mut() {
+            Some(view) => view,
+            None => return Ok(()),
+        };
+
+        execute!(
+            stdout(),
+            Print(
+                theme::get()
+                    .title
+                    .apply(shorten(&escape(&view.title), width.saturating_sub(3)))
+            ),
+            MoveToNextLine(1)
+        )?;
+
+        let visible = height.saturating_sub(2);
+        view.scroll = view
+            .scroll
+            .min(layout::max_scroll(view.lines.len(), visible));
+        for (line, note) in view.lines.iter().skip(view.scroll).take(visible) {
+            let note = note.as_deref().map(escape);
+            let note_len = note.as_ref().map_or(0, |n| render::width(n) + 1);
+            let line = shorten(
+                &format!("  {}", escape(line)),
+                width.saturating_sub(note_len + 3).max(3),
+            );
+            execute!(stdout(), Print(line))?;
+            if let Some(note) = note {
+                execute!(
+                    stdout(),
+                    Print(theme::get().note.apply(format!(" {}", note)))
+                )?;
+            }
+            execute!(stdout(), MoveToNextLine(1))?;
+        }
+
+        Ok(())
+    }
+
+    fn print_transfer_prompt(&mut self) -> Result<()> {
+        let plan = match self.pending_transfer.as_ref() {
+            Some(plan) => plan,
+            None => return Ok(()),
+        };
+
+        let message = match plan.conflicts() {
+            0 => format!(
+                "[Y]es to {} {} keys    [N]o to cancel    scroll with arrows or JK",
+                plan.kind.verb(),
+                plan.items.len()
+            ),
+            n => format!(
+                "[O]verwrite or [S]kip the {} existing keys    [N]o to cancel    scroll with arrows or JK",
+                n
+            ),
+        };
+
+        self.print_info(&message)
+    }
+
+    fn print_message_raw(&mut self, message: StyledContent<String>) -> Result<()> {
+        if self
+            .displayed_message
+            .as_ref()
+            .is_some_and(|m| m.content() == message.content())
+        {
+            return Ok(());
+        }
+
+        self.displayed_message = Some(message.clone());
+        print_message(message)
+    }
+
+    fn print_info(&mut self, message: &str) -> Result<()> {
+        self.print_message_raw(theme::get().info.apply(format!(" {} ", message)))
     }
 
     fn print_error(&mut self, err: Error) -> Result<()> {
-        self.print_message_raw(format!(" {} ", err).white().on_red())
+        self.print_message_raw(theme::get().error.apply(format!(" {} ", err)))
     }
 
+    /// Remind how to show the key bindings.
     fn print_controls(&mut self) -> Result<()> {
-        self.print_info(
-            "Navigate with arrows or HJKL    copy [P]ath    copy [S]ecret    [A]dd secret    [R]ename key    [U]pdate secret    [D]elete secret    [Q]uit    [C]lear cache    [O]pen help",
-        )
+        let controls: Vec<String> = [Action::Help, Action::Quit]
+            .iter()
+            .filter_map(|&action| {
+                let key = self.keymap.keys(action).into_iter().next()?;
+                Some(format!("[{}] {}", key, action.description()))
+            })
+            .collect();
+        self.print_info(&controls.join("    "))
+    }
+
+    /// Every action with its keys, from the keymap in use, grouped by category.
+    fn open_help(&mut self) -> Result<()> {
+        let keys: Vec<(Action, String)> = Action::ALL
+            .iter()
+            .map(|&action| match self.keymap.describe(action) {
+                keys if keys.is_empty() => (action, "-".to_owned()),
+                keys => (action, keys),
+            })
+            .collect();
+        let width = keys
+            .iter()
+            .map(|(_, keys)| render::width(keys))
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = vec![];
+        for category in Category::ALL {
+            if !lines.is_empty() {
+                lines.push((String::new(), None));
+            }
+            lines.push((category.name().to_owned(), None));
+            for (action, keys) in keys.iter().filter(|(a, _)| a.category() == category) {
+                let padding = " ".repeat(width - render::width(keys));
+                let line = format!("  {}{}  {}", keys, padding, action.description());
+                lines.push((line, None));
+            }
+        }
+        if self.config.mouse() {
+            lines.push((String::new(), None));
+            lines.push(("mouse".to_owned(), None));
+            lines.push((
+                "  click to select, double click to open, wheel to scroll".to_owned(),
+                None,
+            ));
+            lines.push((
+                "  hold Shift to select text in the terminal".to_owned(),
+                None,
+            ));
+        }
+
+        self.list_view = Some(ListView::new("key bindings".to_owned(), lines));
+        self.mode = Mode::ShowingReport;
+        self.print()?;
+        self.displayed_message = None;
+        self.print_info("scroll with arrows or JK    any other key to close")
     }
 
     fn handle_navigation(&mut self) -> Result<()> {
-        let mut needs_refresh = false;
-        if let Event::Key(event) = read()? {
+        let event = self.read_event()?;
+        if let Event::Mouse(event) = event {
+            return self.handle_mouse(event);
+        }
+        if let Event::Key(event) = event {
             if event.kind != KeyEventKind::Press {
                 return Ok(());
             }
-            match event.code {
-                KeyCode::Down | KeyCode::Char('j') => {
-                    if self.selected_item < self.current_list.len() - 1 {
-                        self.selected_item += 1;
-                    }
-                    needs_refresh = true;
+            let page_rows = self.page_rows()?;
+            if self.handle_key(event, page_rows)? {
+                if self.mode == Mode::Navigation {
+                    self.update_selected_secret(FromCache::Yes)?;
                 }
-                KeyCode::Up | KeyCode::Char('k') => {
-                    if self.selected_item > 0 {
-                        self.selected_item -= 1;
-                    }
-                    needs_refresh = true;
+
+                self.print()?;
+                self.displayed_message = None;
+            } else if let Some(count) = self.count {
+                self.print_info(&count.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply a key pressed in the listing, returns whether the screen must be drawn again.
+    fn handle_key(&mut self, event: KeyEvent, page_rows: usize) -> Result<bool> {
+        let mut needs_refresh = false;
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        let action = self.keymap.action(&event);
+        // everything is gone after deleting or moving the last keys of the root
+        if self.current_list.is_empty()
+            && !action.is_some_and(Action::allowed_when_empty)
+            && !(ctrl && event.code == KeyCode::Char('c'))
+            && event.code != KeyCode::Esc
+        {
+            return Ok(false);
+        }
+        // a number typed before a move repeats it, e.g. `5j`, or selects an entry, e.g. `5G`
+        if let KeyCode::Char(digit @ '0'..='9') = event.code {
+            if digit != '0' || self.count.is_some() {
+                let count = self
+                    .count
+                    .unwrap_or(0)
+                    .saturating_mul(10)
+                    .saturating_add(digit as usize - '0' as usize);
+                self.count = Some(count);
+                return Ok(false);
+            }
+        }
+        let count = self.count.take();
+        let repeat = count.unwrap_or(1);
+
+        let selected_is_dir = self
+            .current_list
+            .get(self.selected_item)
+            .is_some_and(|e| e.is_dir);
+        match action {
+            // Ctrl+C and Esc with a selection or a filter keep their meaning whatever the keymap
+            _ if ctrl && event.code == KeyCode::Char('c') => self.quit_requested = true,
+            _ if event.code == KeyCode::Esc && !self.selection.is_empty() => {
+                self.selection.clear();
+
+                needs_refresh = true;
+            }
+            _ if event.code == KeyCode::Esc && self.filter.is_some() => {
+                self.clear_filter()?;
+
+                needs_refresh = true;
+            }
+            Some(Action::Down) => {
+                self.select(self.selected_item.saturating_add(repeat));
+                needs_refresh = true;
+            }
+            Some(Action::Up) => {
+                self.select(self.selected_item.saturating_sub(repeat));
+                needs_refresh = true;
+            }
+            Some(Action::PageDown) => {
+                self.select(self.selected_item.saturating_add(page_rows * repeat));
+                needs_refresh = true;
+            }
+            Some(Action::PageUp) => {
+                self.select(self.selected_item.saturating_sub(page_rows * repeat));
+                needs_refresh = true;
+            }
+            Some(Action::HalfPageDown) => {
+                self.select(
+                    self.selected_item
+                        .saturating_add(page_rows.div_ceil(2) * repeat),
+                );
+                needs_refresh = true;
+            }
+            Some(Action::HalfPageUp) => {
+                self.select(
+                    self.selected_item
+                        .saturating_sub(page_rows.div_ceil(2) * repeat),
+                );
+                needs_refresh = true;
+            }
+            Some(Action::First) => {
+                self.select(count.map_or(0, |n| n - 1));
+                needs_refresh = true;
+            }
+            Some(Action::Last) => {
+                self.select(count.map_or(usize::MAX, |n| n - 1));
+                needs_refresh = true;
+            }
+            Some(Action::Open) if selected_is_dir => {
+                self.enter_selected()?;
+                needs_refresh = true;
+            }
+            // in the pick mode, the other keys of `open` do nothing on a key
+            Some(Action::Open) if self.pick.is_some() && event.code == KeyCode::Enter => {
+                return self.pick_selected().map(|()| false)
+            }
+            Some(Action::Back) => {
+                self.go_up()?;
+                needs_refresh = true;
+            }
+            Some(Action::ClearCache) => {
+                self.client.clear_cache();
+                self.invalidate_search_index();
+                self.update_list(FromCache::Yes)?;
+                self.update_selected_secret(FromCache::Yes)?;
+
+                needs_refresh = true;
+            }
+            Some(Action::Help) => return self.open_help().map(|()| false),
+            Some(Action::Reveal) => {
+                self.reveal = !self.reveal;
+
+                needs_refresh = true;
+            }
+            Some(Action::Details) => {
+                if selected_is_dir {
+                    return Err(Error::Application(
+                        "a directory has no details, please select a key".to_owned(),
+                    ));
                 }
-                KeyCode::Right | KeyCode::Char('l') => {
-                    if self.path.entries.len() > 32 {
-                        return Ok(());
-                    }
-                    let entry = self.current_list[self.selected_item].clone();
-                    if !entry.is_dir {
-                        return Ok(());
-                    }
-                    self.path.entries.push(entry);
-                    self.update_list(FromCache::Yes)?;
-                    self.selected_item = self.selected_item.min(self.current_list.len() - 1);
-                    self.scroll = 0;
-                    needs_refresh = true;
+                return self.open_details().map(|()| false);
+            }
+            Some(Action::Profiles) => return self.open_profile_picker().map(|()| false),
+            Some(Action::CopyPath) => {
+                let entries = self.selected_entries();
+                if entries.is_empty() {
+                    let path = self.get_selected_path();
+                    self.clipboard.copy(path)?;
+
+                    self.print_info("path copied to clipboard")?;
+                } else {
+                    let directory = self.path.join();
+                    let paths: Vec<String> = entries
+                        .iter()
+                        .map(|e| format!("{}{}", directory, e))
+                        .collect();
+                    self.clipboard.copy(paths.join("\n"))?;
+
+                    self.print_info(&format!("{} paths copied to clipboard", paths.len()))?;
                 }
-                KeyCode::Left | KeyCode::Char('h') => {
-                    if self.path.entries.len() < self.root_len + 1 {
-                        return Ok(());
+            }
+            Some(Action::ToggleSelection) => {
+                for _ in 0..repeat {
+                    self.toggle_selection(self.selected_item);
+                    if self.selected_item + 1 >= self.current_list.len() {
+                        break;
                     }
-                    let last = self.path.entries.pop().unwrap();
-                    self.set_selected_item(&last.name, FromCache::Yes)?;
-                    self.scroll = 0;
-                    needs_refresh = true;
+                    self.select(self.selected_item + 1);
                 }
-                KeyCode::Char('c') => {
-                    if event.modifiers.contains(KeyModifiers::CONTROL) {
-                        self.quit_requested = true
-                    } else {
-                        self.client.clear_cache();
-                        self.update_list(FromCache::Yes)?;
-                        self.update_selected_secret(FromCache::Yes)?;
+                needs_refresh = true;
+            }
+            Some(Action::SelectAll) => {
+                let sources: Vec<String> =
+                    self.current_list.iter().map(VaultEntry::source).collect();
+                self.selection.extend(sources);
+                needs_refresh = true;
+            }
+            Some(Action::InvertSelection) => {
+                for index in 0..self.current_list.len() {
+                    let source = self.current_list[index].source();
+                    if !self.selection.remove(&source) {
+                        self.selection.insert(source);
                     }
+                }
+                needs_refresh = true;
+            }
+            Some(Action::SelectRange) => {
+                self.select_range(self.selected_item);
+                needs_refresh = true;
+            }
+            Some(Action::Export) => {
+                self.mode = Mode::TypingExportFile;
 
-                    needs_refresh = true;
+                needs_refresh = true;
+            }
+            Some(Action::CopySecret) => {
+                let entry = &self.current_list[self.selected_item];
+                if entry.is_dir {
+                    return Ok(false);
                 }
-                KeyCode::Char('o') => {
-                    self.print_controls()?;
+
+                if let Some(secret) = self.selected_secret.as_ref() {
+                    let message = match self.clipboard.copy_secret((&**secret).into())? {
+                        Some(after) => format!(
+                            "secret copied to clipboard, it will be removed in {}s",
+                            after.as_secs()
+                        ),
+                        None => "secret copied to clipboard".to_owned(),
+                    };
+                    self.print_info(&message)?;
                 }
-                KeyCode::Char('p') if self.clipboard.is_some() => {
-                    let path = self.get_selected_path();
-                    self.clipboard.as_mut().unwrap().set_contents(path).unwrap();
+            }
+            Some(Action::Add) => {
+                self.previous_selected_item = self.selected_item;
+                self.selected_item = self.current_list.len();
+                self.mode = Mode::TypingKey(EditMode::Insert);
 
-                    self.print_info("path copied to clipboard")?;
+                needs_refresh = true;
+            }
+            Some(Action::Update) => {
+                let entry = &self.current_list[self.selected_item];
+                if entry.is_dir {
+                    return Err(Error::Application(
+                        "cannot update a directory, please select a key".to_owned(),
+                    ));
+                }
+
+                self.mode = Mode::TypingSecret(EditMode::Update);
+
+                needs_refresh = true;
+            }
+            Some(Action::Rename) => {
+                self.mode = Mode::TypingKey(EditMode::Update);
+
+                needs_refresh = true;
+            }
+            Some(Action::Move) => {
+                self.mode = Mode::TypingDestination(TransferKind::Move);
+
+                needs_refresh = true;
+            }
+            Some(Action::Copy) => {
+                self.mode = Mode::TypingDestination(TransferKind::Copy);
+
+                needs_refresh = true;
+            }
+            Some(Action::Search) => {
+                let root = self.root();
+                if self.search_index.as_ref().map(|i| i.root()) != Some(root.as_str()) {
+                    self.search_index = Some(TreeIndex::new(self.client.clone(), &root));
+                }
+                let mut view = SearchView::new();
+                view.update_results(&self.search_index.as_ref().unwrap().keys);
+                self.search_view = Some(view);
+                self.mode = Mode::Searching;
+                self.print()?;
+                self.displayed_message = None;
+                return self
+                    .print_info(self.search_view.as_ref().unwrap().controls())
+                    .map(|()| false);
+            }
+            Some(Action::Filter) => {
+                if self.filter.is_none() {
+                    self.filter = Some(ListFilter::new("")?);
+                    self.filtered_directory = self.path.join();
+                }
+                self.mode = Mode::Filtering;
+                self.print()?;
+                return self.print_filter_prompt().map(|()| false);
+            }
+            Some(Action::Bookmark) => {
+                self.mode = Mode::TypingBookmarkName;
+
+                needs_refresh = true;
+            }
+            Some(Action::Jump) => return self.open_jump_picker().map(|()| false),
+            Some(Action::Grep) => {
+                self.mode = Mode::TypingGrepPattern;
+
+                needs_refresh = true;
+            }
+            Some(Action::Compare) => {
+                if !self.current_list[self.selected_item].is_dir {
+                    return Err(Error::Application(
+                        "cannot compare a key, please select a directory".to_owned(),
+                    ));
+                }
+                self.mode = Mode::TypingComparedPath;
+
+                needs_refresh = true;
+            }
+            Some(Action::Delete) => {
+                let entries = self.selected_entries();
+                if !entries.is_empty() {
+                    let plan =
+                        DeletePlan::for_entries(&mut self.client, &self.path.join(), &entries)?;
+                    return self.preview_delete(plan).map(|()| false);
+                }
+                if self.current_list[self.selected_item].is_dir {
+                    let directory = self.get_selected_source();
+                    let plan = DeletePlan::new(&mut self.client, &directory)?;
+                    return self.preview_delete(plan).map(|()| false);
+                }
+                self.mode = Mode::DeletingKey;
+
+                needs_refresh = true;
+            }
+            Some(Action::Undo) => return self.preview_undo().map(|()| false),
+            Some(Action::Quit) => self.quit_requested = true,
+            Some(Action::Open) | None => (),
+        }
+
+        Ok(needs_refresh)
+    }
+
+    /// Wait for the next event, the screen is drawn again when the terminal is resized.
+    fn read_event(&mut self) -> Result<Event> {
+        let event = read()?;
+        if let Event::Resize(_, _) = event {
+            self.redraw()?;
+        }
+
+        Ok(event)
+    }
+
+    /// Draw the screen and the last message again.
+    fn redraw(&mut self) -> Result<()> {
+        let message = self.displayed_message.take();
+        self.print()?;
+        // `print` leaves the cursor where a key is typed in the list
+        let typing_at = match self.is_typing_in_list() {
+            true => Some(cursor::position()?),
+            false => None,
+        };
+        if let Some(message) = message {
+            self.print_message_raw(message)?;
+        }
+        if let Some((column, row)) = typing_at {
+            execute!(stdout(), MoveTo(column, row))?;
+        }
+
+        Ok(())
+    }
+
+    /// Click to select an entry, double click to open it, and scroll with the wheel.
+    fn handle_mouse(&mut self, event: MouseEvent) -> Result<()> {
+        match event.kind {
+            MouseEventKind::ScrollDown => self.select(self.selected_item.saturating_add(1)),
+            MouseEventKind::ScrollUp => self.select(self.selected_item.saturating_sub(1)),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let (width, height) = terminal::size()?;
+                let layout = Layout::new(width as usize, height as usize);
+                let index = match layout.list_row_at(event.column as usize, event.row as usize) {
+                    Some(row) if self.scroll + row < self.current_list.len() => self.scroll + row,
+                    _ => return Ok(()),
+                };
+                let double = self
+                    .last_click
+                    .take()
+                    .is_some_and(|(at, i)| i == index && at.elapsed() < DOUBLE_CLICK);
+                self.select(index);
+                if !double {
+                    self.last_click = Some((Instant::now(), index));
+                } else if self.current_list[index].is_dir {
+                    self.enter_selected()?;
+                } else {
+                    return self.open_details();
+                }
+            }
+            _ => return Ok(()),
+        }
+
+        self.update_selected_secret(FromCache::Yes)?;
+        self.print()?;
+        self.displayed_message = None;
+
+        Ok(())
+    }
+
+    /// Read a line typed at the cursor, `None` when it is cancelled with Esc.
+    fn read_line(&mut self, initial: &str) -> Result<Option<String>> {
+        let line = LineEditor::new(initial)
+            .with_deadline(self.clipboard.clear_deadline())
+            .read(|interruption| self.interrupt_typing(interruption))?;
+        // the prompt may have been drawn over
+        self.displayed_message = None;
+
+        Ok(line)
+    }
+
+    /// Redraw on resize and remove the copied secret from the clipboard in time while typing.
+    fn interrupt_typing(&mut self, interruption: Interruption) -> Result<()> {
+        match interruption {
+            Interruption::Resize => self.redraw(),
+            Interruption::Deadline => self.clipboard.clear(),
+        }
+    }
+
+    /// Read the value of a secret, hidden unless the values are revealed.
+    fn read_secret(&mut self, initial: &str) -> Result<Option<String>> {
+        if !self.reveal {
+            // the hint is printed at the bottom, the value is typed next to the key
+            let origin = cursor::position()?;
+            self.print_info("the value is hidden, [Ctrl+R] to reveal it")?;
+            execute!(stdout(), MoveTo(origin.0, origin.1))?;
+        }
+        let line = LineEditor::new(initial)
+            .with_mask(!self.reveal)
+            .with_deadline(self.clipboard.clear_deadline())
+            .read(|interruption| self.interrupt_typing(interruption))?;
+        self.displayed_message = None;
+
+        Ok(line)
+    }
+
+    /// Go back to the navigation, dropping the key being added.
+    fn cancel_typing(&mut self) -> Result<()> {
+        self.mode = Mode::Navigation;
+        self.buffered_key.clear();
+        if self.selected_item >= self.current_list.len() {
+            self.selected_item = self.previous_selected_item;
+        }
+        self.print()?;
+        self.print_info("cancelled")
+    }
+
+    fn handle_typing_key(&mut self, edit_mode: EditMode) -> Result<()> {
+        let initial = match edit_mode {
+            EditMode::Insert => String::new(),
+            EditMode::Update => self.current_list[self.selected_item].name.clone(),
+        };
+        let key = match self.read_line(&initial)? {
+            Some(key) => key,
+            None => return self.cancel_typing(),
+        };
+        if key.is_empty() {
+            self.mode = Mode::Navigation;
+            self.buffered_key.clear();
+            self.selected_item = self.previous_selected_item;
+
+            return Err(Error::Application("the key must not be empty".to_owned()));
+        }
+
+        if key.ends_with('/') {
+            self.mode = Mode::Navigation;
+            self.buffered_key.clear();
+            self.selected_item = self.previous_selected_item;
+
+            return Err(Error::Application(
+                "to create a directory, please specify the key name, e.g. directory/keyName"
+                    .to_owned(),
+            ));
+        }
+
+        match edit_mode {
+            EditMode::Insert => {
+                self.buffered_key = key;
+                self.mode = Mode::TypingSecret(EditMode::Insert);
+                self.print()
+            }
+            EditMode::Update => {
+                self.mode = Mode::Navigation;
+                self.rename_key(&key)
+            }
+        }
+    }
+
+    fn handle_typing_secret(&mut self, secret_type: EditMode) -> Result<()> {
+        let initial = Zeroizing::new(match secret_type {
+            EditMode::Insert => String::new(),
+            EditMode::Update => self
+                .selected_secret
+                .as_ref()
+                .and_then(|s| s.single_value())
+                .map(String::from)
+                .unwrap_or_default(),
+        });
+        let secret = match self.read_secret(&initial)? {
+            Some(secret) => Zeroizing::new(VaultSecret::new(&Zeroizing::new(secret))),
+            None => return self.cancel_typing(),
+        };
+        self.mode = Mode::Navigation;
+        let key = match secret_type {
+            EditMode::Insert => self.buffered_key.clone(),
+            EditMode::Update => self.current_list[self.selected_item].name.clone(),
+        };
+        let path = format!("{}{}", self.path.join(), key);
+
+        let before = read_state(&mut self.client, &path);
+        self.client.write_secret_data(&path, &secret)?;
+        let change = before.and_then(|before| {
+            let description = format!(
+                "{} of '{}'",
+                match before {
+                    Some(_) => "update",
+                    None => "addition",
+                },
+                path
+            );
+            let after = snapshot(Some(&secret))?;
+            Ok(Change::new(
+                description,
+                vec![(path.clone(), before, after)],
+            ))
+        });
+        let note = self.undo.record(change);
+        self.invalidate_search_index();
+        self.set_selected_item(&key, FromCache::No)?;
+        self.print()?;
+
+        match secret_type {
+            EditMode::Insert => self.print_info(&format!("added the key '{}'{}", path, note))?,
+            EditMode::Update => {
+                self.print_info(&format!("updated the secret of '{}'{}", path, note))?
+            }
+        }
+
+        self.buffered_key.clear();
+
+        Ok(())
+    }
+
+    fn handle_typing_destination(&mut self, kind: TransferKind) -> Result<()> {
+        let entries = self.selected_entries();
+        let source = self.get_selected_source();
+        let (prompt, default) = match entries.is_empty() {
+            true => (
+                format!(
+                    "{} '{}' to (full path, end with '/' to keep the name): ",
+                    kind.verb(),
+                    source
+                ),
+                source.clone(),
+            ),
+            false => (
+                format!(
+                    "{} the {} selected entries to the directory: ",
+                    kind.verb(),
+                    entries.len()
+                ),
+                self.path.join(),
+            ),
+        };
+        self.print_info(&prompt)?;
+        execute!(stdout(), Print(" "))?;
+
+        let destination = match self.read_line(&default)? {
+            Some(destination) => destination,
+            None => return self.cancel_typing(),
+        };
+        self.mode = Mode::Navigation;
+        if destination.is_empty() {
+            return Err(Error::Application(
+                "the destination must not be empty".to_owned(),
+            ));
+        }
+
+        let plan = match entries.is_empty() {
+            true => TransferPlan::new(&mut self.client, kind, &source, &destination)?,
+            false => TransferPlan::for_entries(
+                &mut self.client,
+                kind,
+                &self.path.join(),
+                &entries,
+                &destination,
+            )?,
+        };
+        self.preview_transfer(plan)
+    }
+
+    fn handle_confirming_transfer(&mut self) -> Result<()> {
+        let code = match self.read_list_view_key()? {
+            Some(code) => code,
+            None => return Ok(()),
+        };
+        let policy = match code {
+            KeyCode::Char('y') | KeyCode::Enter => ConflictPolicy::Abort,
+            KeyCode::Char('o') => ConflictPolicy::Overwrite,
+            KeyCode::Char('s') => ConflictPolicy::Skip,
+            KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+                self.pending_transfer = None;
+                return self.close_list_view(Some("cancelled"));
+            }
+            _ => return Ok(()),
+        };
+
+        self.mode = Mode::Navigation;
+        self.list_view = None;
+        let plan = self.pending_transfer.take().unwrap();
+        let description = match plan.entries {
+            Some(entries) => format!(
+                "{} of {} entries of '{}' to '{}'",
+                plan.kind.verb(),
+                entries,
+                plan.source,
+                plan.destination
+            ),
+            None => format!(
+                "{} of '{}' to '{}'",
+                plan.kind.verb(),
+                plan.source,
+                plan.destination
+            ),
+        };
+        let change = Change::for_transfer(&mut self.client, description, &plan, policy);
+        let res = plan.execute(&mut self.client, policy);
+        // a failed transfer is rolled back, or leaves keys that cannot be undone as one change
+        let note = match res {
+            Ok(_) => self.undo.record(change),
+            Err(_) => "",
+        };
+        self.invalidate_search_index();
+
+        // the tree may have changed even if the transfer failed
+        let selected = match plan.destination.strip_prefix(&self.path.join()) {
+            Some(rest) => rest.split('/').next().unwrap_or_default().to_owned(),
+            None => self.current_list[self.selected_item].name.clone(),
+        };
+        self.previous_selected_item = self.selected_item;
+        self.climb_to_existing()?;
+        self.set_selected_item(&selected, FromCache::No)?;
+        self.print()?;
+
+        let report = res?;
+        if plan.entries.is_some() {
+            self.selection.clear();
+        }
+        self.print_info(&format!(
+            "{} {} keys to '{}'{}{}",
+            match plan.kind {
+                TransferKind::Copy => "copied",
+                TransferKind::Move => "moved",
+            },
+            report.transferred,
+            plan.destination,
+            match report.skipped {
+                0 => String::new(),
+                n => format!(", skipped {} existing keys", n),
+            },
+            note
+        ))
+    }
+
+    /// Wait for a key press while a list view is shown, the scrolling keys are handled here.
+    fn read_list_view_key(&mut self) -> Result<Option<KeyCode>> {
+        let code = match self.read_event()? {
+            Event::Key(event) if event.kind == KeyEventKind::Press => event.code,
+            _ => return Ok(None),
+        };
+
+        if let Some(view) = self.list_view.as_mut() {
+            if view.handle_scroll(code) {
+                self.print()?;
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(code))
+    }
+
+    fn close_list_view(&mut self, message: Option<&str>) -> Result<()> {
+        self.mode = Mode::Navigation;
+        self.list_view = None;
+        self.print()?;
+        match message {
+            Some(message) => self.print_info(message),
+            None => Ok(()),
+        }
+    }
+
+    /// Show the keys of a directory or of the selection before deleting them.
+    fn preview_delete(&mut self, plan: DeletePlan) -> Result<()> {
+        self.list_view = Some(ListView::new(
+            match plan.entries {
+                Some(entries) => format!(
+                    "delete {} entries of {} ({} keys)",
+                    entries,
+                    plan.directory,
+                    plan.keys.len()
+                ),
+                None => format!("delete {} ({} keys)", plan.directory, plan.keys.len()),
+            },
+            plan.keys.iter().map(|k| (k.clone(), None)).collect(),
+        ));
+        self.mode = Mode::ConfirmingDirectoryDelete;
+        self.print()?;
+        self.displayed_message = None;
+        self.print_info(&match plan.version {
+            KvVersion::V1 => format!(
+                "[Y]es to delete {} keys    [N]o to cancel    scroll with arrows or JK",
+                plan.keys.len()
+            ),
+            KvVersion::V2 => format!(
+                "[S]oft delete or delete the [M]etadata and all versions of {} keys    [N]o to cancel    scroll with arrows or JK",
+                plan.keys.len()
+            ),
+        })?;
+        self.pending_delete = Some(plan);
+
+        Ok(())
+    }
+
+    fn handle_confirming_directory_delete(&mut self) -> Result<()> {
+        let version = match self.pending_delete.as_ref() {
+            Some(plan) => plan.version,
+            None => return self.close_list_view(None),
+        };
+        let code = match self.read_list_view_key()? {
+            Some(code) => code,
+            None => return Ok(()),
+        };
+
+        let mode = match (version, code) {
+            (KvVersion::V1, KeyCode::Char('y') | KeyCode::Enter) => DeleteMode::Metadata,
+            (KvVersion::V2, KeyCode::Char('s')) => DeleteMode::Soft,
+            (KvVersion::V2, KeyCode::Char('m')) => DeleteMode::Metadata,
+            (_, KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc) => {
+                self.pending_delete = None;
+                return self.close_list_view(Some("cancelled"));
+            }
+            _ => return Ok(()),
+        };
+
+        self.mode = Mode::TypingDirectoryDelete(mode);
+
+        Ok(())
+    }
+
+    fn handle_typing_directory_delete(&mut self, mode: DeleteMode) -> Result<()> {
+        let plan = match self.pending_delete.take() {
+            Some(plan) => plan,
+            None => return self.close_list_view(None),
+        };
+        // the whole directory is confirmed with its path, the selection with `yes`
+        let expected = match plan.entries {
+            Some(_) => "yes",
+            None => plan.directory.as_str(),
+        };
+        self.print_info(&match plan.entries {
+            Some(entries) => format!(
+                "type 'yes' to delete the {} keys of the {} selected entries: ",
+                plan.keys.len(),
+                entries
+            ),
+            None => format!(
+                "type the full path of the directory '{}' to confirm: ",
+                plan.directory
+            ),
+        })?;
+        execute!(stdout(), Print(" "))?;
+
+        let answer = match self.read_line("")? {
+            Some(answer) => answer,
+            None => return self.close_list_view(Some("cancelled")),
+        };
+        if answer != expected {
+            self.close_list_view(None)?;
+            return Err(Error::Application(format!(
+                "received '{}', nothing was deleted",
+                answer
+            )));
+        }
+
+        let total = plan.keys.len();
+        let description = match plan.entries {
+            Some(entries) => format!("deletion of {} entries of '{}'", entries, plan.directory),
+            None => format!("deletion of '{}'", plan.directory),
+        };
+        // the keys are read first to be able to undo the deletion
+        let change = Change::for_delete(&mut self.client, description, &plan, |done| {
+            let _ = print_message(
+                theme::get()
+                    .info
+                    .apply(format!(" reading {}/{} ", done, total)),
+            );
+        });
+        self.invalidate_search_index();
+        let report = plan.execute(&mut self.client, mode, |done| {
+            let _ = print_message(
+                theme::get()
+                    .info
+                    .apply(format!(" deleting {}/{} ", done, total)),
+            );
+        });
+        self.displayed_message = None;
+        let failed: Vec<&str> = report.failed.iter().map(|(key, _)| key.as_str()).collect();
+        let note = self
+            .undo
+            .record(change.map(|change| change.without(&failed)));
+
+        self.selection.clear();
+        self.previous_selected_item = self.selected_item;
+        let selected = self.current_list[self.selected_item].name.clone();
+        self.climb_to_existing()?;
+        self.set_selected_item(&selected, FromCache::No)?;
+
+        let summary = format!(
+            "deleted {} keys under '{}'{}{}",
+            report.deleted,
+            plan.directory,
+            note,
+            match report.failed.len() {
+                0 => String::new(),
+                n => format!(", {} failed, press any key to continue", n),
+            }
+        );
+        if report.failed.is_empty() {
+            return self.close_list_view(Some(&summary));
+        }
+
+        self.list_view = Some(ListView::new(
+            format!("failed to delete {} keys", report.failed.len()),
+            report
+                .failed
+                .iter()
+                .map(|(key, err)| (key.clone(), Some(err.to_string())))
+                .collect(),
+        ));
+        self.mode = Mode::ShowingReport;
+        self.print()?;
+        self.print_info(&summary)
+    }
+
+    fn handle_typing_export_file(&mut self) -> Result<()> {
+        let mut entries = self.selected_entries();
+        if entries.is_empty() {
+            entries.push(self.current_list[self.selected_item].source());
+        }
+        let directory = self.path.join();
+        self.print_info(&match entries.as_slice() {
+            [entry] => format!(
+                "export the secrets of '{}{}' to the file: ",
+                directory, entry
+            ),
+            _ => format!(
+                "export the secrets of the {} selected entries to the file: ",
+                entries.len()
+            ),
+        })?;
+        execute!(stdout(), Print(" "))?;
+
+        let file = match self.read_line("")? {
+            Some(file) => file,
+            None => return self.cancel_typing(),
+        };
+        self.mode = Mode::Navigation;
+        if file.is_empty() {
+            return Err(Error::Application(
+                "the file name must not be empty".to_owned(),
+            ));
+        }
+
+        let exported = export_entries(&mut self.client, &directory, &entries, &file)?;
+        self.print()?;
+        self.print_info(&format!("exported {} keys to '{}'", exported, file))
+    }
+
+    /// Show the keys that undoing the last change restores, and the ones changed since.
+    fn preview_undo(&mut self) -> Result<()> {
+        let change = match self.undo.last() {
+            Some(change) => change,
+            None => return Err(Error::Application("nothing to undo".to_owned())),
+        };
+        let modified = change.modified_since(&mut self.client)?;
+        let lines = change
+            .keys()
+            .map(|(path, created)| {
+                (
+                    format!(
+                        "{} {}",
+                        match created {
+                            true => "delete",
+                            false => "restore",
+                        },
+                        path
+                    ),
+                    modified
+                        .iter()
+                        .any(|m| m == path)
+                        .then(|| "(changed since)".to_owned()),
+                )
+            })
+            .collect();
+        let title = format!("undo the {}", change.description);
+
+        self.list_view = Some(ListView::new(title, lines));
+        self.mode = Mode::ConfirmingUndo;
+        self.print()?;
+        self.displayed_message = None;
+        self.print_info(&match modified.len() {
+            0 => "[Y]es to undo    [N]o to cancel    scroll with arrows or JK".to_owned(),
+            n => format!(
+                "{} keys were changed since, [O]verwrite them    [N]o to cancel    scroll with arrows or JK",
+                n
+            ),
+        })
+    }
+
+    fn handle_confirming_undo(&mut self) -> Result<()> {
+        let code = match self.read_list_view_key()? {
+            Some(code) => code,
+            None => return Ok(()),
+        };
+        let overwrite = match code {
+            KeyCode::Char('y') | KeyCode::Enter => false,
+            KeyCode::Char('o') => true,
+            KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+                return self.close_list_view(Some("cancelled"));
+            }
+            _ => return Ok(()),
+        };
+
+        self.mode = Mode::Navigation;
+        self.list_view = None;
+        // the change stays on the stack until every key is restored
+        let change = match self.undo.last_mut() {
+            Some(change) => change,
+            None => return self.close_list_view(None),
+        };
+        let description = change.description.clone();
+        // the keys may have changed while the preview was shown
+        if !overwrite && !change.modified_since(&mut self.client)?.is_empty() {
+            self.print()?;
+            return Err(Error::Application(format!(
+                "the keys of the {} were changed since, nothing was undone",
+                description
+            )));
+        }
+
+        // select the restored key when it is in the current directory
+        let directory = self.path.join();
+        let selected = change
+            .keys()
+            .filter(|(_, created)| !created)
+            .find_map(|(path, _)| path.strip_prefix(&directory))
+            .map(|rest| rest.split('/').next().unwrap_or_default().to_owned());
+        let res = change.revert(&mut self.client);
+        if res.is_ok() {
+            self.undo.pop();
+        }
+        self.invalidate_search_index();
+
+        let selected = selected.unwrap_or_else(|| {
+            self.current_list
+                .get(self.selected_item)
+                .map_or(String::new(), |e| e.name.clone())
+        });
+        self.previous_selected_item = self.selected_item;
+        self.climb_to_existing()?;
+        self.set_selected_item(&selected, FromCache::No)?;
+        self.print()?;
+
+        res.map_err(|err| Error::Application(format!("{}, undo again to retry", err)))?;
+        self.print_info(&format!("undid the {}", description))
+    }
+
+    fn handle_showing_report(&mut self) -> Result<()> {
+        match self.read_list_view_key()? {
+            Some(_) => self.close_list_view(None),
+            None => Ok(()),
+        }
+    }
+
+    fn print_details(&mut self) -> Result<()> {
+        let details = match self.details.as_ref() {
+            Some(details) => details,
+            None => return Ok(()),
+        };
+        let scroll = self.list_view.as_ref().map_or(0, |v| v.scroll);
+        let mut view = ListView::new(
+            format!("details of {}", self.get_selected_path()),
+            detail_lines(details, self.reveal),
+        );
+        view.scroll = scroll;
+        self.list_view = Some(view);
+        self.print()?;
+        self.displayed_message = None;
+        self.print_info("[V] reveal values    scroll with arrows or JK    any other key to close")
+    }
+
+    fn handle_showing_details(&mut self) -> Result<()> {
+        match self.read_list_view_key()? {
+            Some(KeyCode::Char('v')) => {
+                self.reveal = !self.reveal;
+                self.print_details()
+            }
+            Some(_) => {
+                self.details = None;
+                self.close_list_view(None)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn handle_typing_compared_path(&mut self) -> Result<()> {
+        let left = self.get_selected_source();
+        self.print_info(&format!("compare '{}' with (full path): ", left))?;
+        execute!(stdout(), Print(" "))?;
+
+        let mut right = match self.read_line(&left)? {
+            Some(right) => right,
+            None => return self.cancel_typing(),
+        };
+        self.mode = Mode::Navigation;
+        if right.is_empty() {
+            return Err(Error::Application(
+                "the path to compare with must not be empty".to_owned(),
+            ));
+        }
+        if !right.ends_with('/') {
+            right.push('/');
+        }
+
+        let diff = TreeDiff::compute_local(&mut self.client, &left, &right)?;
+        let view = DiffView::new(&diff, &left, &right).with_reveal(self.reveal);
+        let controls = view.controls();
+        self.diff_view = Some(view);
+        self.mode = Mode::ShowingDiff;
+        self.print()?;
+        self.displayed_message = None;
+        self.print_info(controls)
+    }
+
+    fn print_filter_prompt(&mut self) -> Result<()> {
+        let pattern = self.filter.as_ref().map_or("", |f| f.pattern.as_str());
+        let message = format!(
+            "filter: {}    ({} of {} keys)    [Enter] keep the filter    [Esc] clear it",
+            pattern,
+            self.current_list.len(),
+            self.unfiltered_list.len()
+        );
+        self.print_info(&message)
+    }
+
+    fn handle_filtering(&mut self) -> Result<()> {
+        let event = match self.read_event()? {
+            Event::Key(event) if event.kind == KeyEventKind::Press => event,
+            _ => return Ok(()),
+        };
+
+        let mut pattern = self
+            .filter
+            .as_ref()
+            .map_or(String::new(), |f| f.pattern.clone());
+        match event.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Navigation;
+                self.clear_filter()?;
+                self.print()?;
+                return self.print_controls();
+            }
+            KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit_requested = true;
+                return Ok(());
+            }
+            KeyCode::Enter => {
+                self.mode = Mode::Navigation;
+                if pattern.is_empty() {
+                    self.clear_filter()?;
                 }
-                KeyCode::Char('s') if self.clipboard.is_some() => {
-                    let entry = &self.current_list[self.selected_item];
-                    if entry.is_dir {
-                        return Ok(());
+                self.print()?;
+                return match self.filter.as_ref() {
+                    Some(filter) => {
+                        let message = format!(
+                            "showing {} of {} keys matching '{}', press Esc to clear the filter",
+                            self.current_list.len(),
+                            self.unfiltered_list.len(),
+                            filter.pattern
+                        );
+                        self.print_info(&message)
                     }
+                    None => self.print_controls(),
+                };
+            }
+            KeyCode::Down if self.selected_item + 1 < self.current_list.len() => {
+                self.selected_item += 1;
+                self.update_selected_secret(FromCache::Yes)?;
+            }
+            KeyCode::Up if self.selected_item > 0 => {
+                self.selected_item -= 1;
+                self.update_selected_secret(FromCache::Yes)?;
+            }
+            KeyCode::Backspace => {
+                pattern.pop();
+                self.filter = Some(ListFilter::new(&pattern)?);
+                self.refilter()?;
+            }
+            KeyCode::Char(c) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+                pattern.push(c);
+                self.filter = Some(ListFilter::new(&pattern)?);
+                self.refilter()?;
+            }
+            _ => return Ok(()),
+        }
 
-                    if let Some(secret) = self.selected_secret.as_ref() {
-                        let secret = secret.into();
-                        self.clipboard
-                            .as_mut()
-                            .unwrap()
-                            .set_contents(secret)
-                            .unwrap();
+        self.print()?;
+        self.displayed_message = None;
+        self.print_filter_prompt()
+    }
+
+    fn open_profile_picker(&mut self) -> Result<()> {
+        if self.config.profiles.is_empty() || self.connect.is_none() {
+            return Err(Error::Application(
+                "no profile is defined in ~/.config/vaultwalker/config.toml".to_owned(),
+            ));
+        }
 
-                        self.print_info("secret copied to clipboard")?;
+        // the connection given at startup comes first, so it can be switched back to
+        self.profile_choices = self
+            .startup_key
+            .as_ref()
+            .map(|_| None)
+            .into_iter()
+            .chain(self.config.profiles.keys().cloned().map(Some))
+            .collect();
+        let items = self
+            .profile_choices
+            .iter()
+            .map(|choice| {
+                let mut notes = match choice.as_ref().and_then(|n| self.config.profiles.get(n)) {
+                    Some(profile) => {
+                        let mut notes = vec![profile
+                            .address
+                            .clone()
+                            .unwrap_or("address from the environment".to_owned())];
+                        if let Some(root) = profile.root.as_ref() {
+                            notes.push(root.clone());
+                        }
+                        if profile.production {
+                            notes.push("production".to_owned());
+                        }
+                        notes
                     }
+                    None => vec!["the flags and environment given at startup".to_owned()],
+                };
+                if &self.profile == choice {
+                    notes.push("current".to_owned());
                 }
-                KeyCode::Char('a') => {
-                    self.previous_selected_item = self.selected_item;
-                    self.selected_item = self.current_list.len();
-                    self.mode = Mode::TypingKey(EditMode::Insert);
+                (
+                    profile_name(choice.as_deref()).to_owned(),
+                    Some(notes.join(", ")),
+                )
+            })
+            .collect();
+        let selected = self
+            .profile_choices
+            .iter()
+            .position(|choice| choice == &self.profile)
+            .unwrap_or(0);
+
+        self.profile_picker = Some(Picker::new(
+            "switch to the profile".to_owned(),
+            items,
+            selected,
+        ));
+        self.mode = Mode::PickingProfile;
+        self.print()?;
+        self.displayed_message = None;
+        self.print_info("[Enter] switch    [R] switch and choose the root path    [Esc] cancel")
+    }
 
-                    needs_refresh = true;
+    fn handle_picking_profile(&mut self) -> Result<()> {
+        let code = match self.read_event()? {
+            Event::Key(event) if event.kind == KeyEventKind::Press => event.code,
+            _ => return Ok(()),
+        };
+        let picker = match self.profile_picker.as_mut() {
+            Some(picker) => picker,
+            None => {
+                self.mode = Mode::Navigation;
+                return Ok(());
+            }
+        };
+        let action = match code {
+            KeyCode::Char('r') => {
+                self.pending_profile = self.profile_choices.get(picker.selected()).cloned();
+                self.profile_picker = None;
+                self.mode = Mode::TypingProfileRoot;
+                return self.print();
+            }
+            code => picker.handle_key(code),
+        };
+        match action {
+            PickerAction::None => self.print(),
+            PickerAction::Close => {
+                self.profile_picker = None;
+                self.mode = Mode::Navigation;
+                self.print()?;
+                self.print_controls()
+            }
+            PickerAction::Select(i) => {
+                self.profile_picker = None;
+                self.mode = Mode::Navigation;
+                match self.profile_choices.get(i).cloned() {
+                    Some(name) => self.switch_profile(name.as_deref(), None),
+                    None => self.print(),
                 }
-                KeyCode::Char('u') => {
-                    let entry = &self.current_list[self.selected_item];
-                    if entry.is_dir {
-                        return Err(Error::Application(
-                            "cannot update a directory, please select a key".to_owned(),
-                        ));
-                    }
+            }
+        }
+    }
 
-                    self.mode = Mode::TypingSecret(EditMode::Update);
+    fn handle_typing_profile_root(&mut self) -> Result<()> {
+        self.mode = Mode::Navigation;
+        let profile = match self.pending_profile.take() {
+            Some(profile) => profile,
+            None => return Ok(()),
+        };
+        self.print_info(&format!(
+            "root path to open with '{}': ",
+            profile_name(profile.as_deref())
+        ))?;
+        execute!(stdout(), Print(" "))?;
 
-                    needs_refresh = true;
-                }
-                KeyCode::Char('r') => {
-                    let entry = &self.current_list[self.selected_item];
-                    if entry.is_dir {
-                        return Err(Error::Application(
-                            "cannot rename a directory, please select a key".to_owned(),
-                        ));
-                    }
+        let initial = profile
+            .as_ref()
+            .and_then(|name| self.config.profiles.get(name))
+            .and_then(|p| p.root.clone())
+            .unwrap_or_default();
+        let root = match self.read_line(&initial)? {
+            Some(root) => root,
+            None => return self.cancel_typing(),
+        };
+        if root.is_empty() {
+            self.print()?;
+            return Err(Error::Application(
+                "the root path must not be empty".to_owned(),
+            ));
+        }
 
-                    self.mode = Mode::TypingKey(EditMode::Update);
+        self.switch_profile(profile.as_deref(), Some(root))
+    }
 
-                    needs_refresh = true;
-                }
-                KeyCode::Char('d') => {
-                    let entry = &self.current_list[self.selected_item];
-                    if entry.is_dir {
-                        return Err(Error::Application(
-                            "cannot delete a directory, please select a key".to_owned(),
-                        ));
-                    }
-                    self.mode = Mode::DeletingKey;
+    /// Connect with another profile, going back to where it was left if it was already used.
+    ///
+    /// `None` is the connection given at startup. Without a root path, the root of the profile
+    /// is used, or the current one.
+    fn switch_profile(&mut self, name: Option<&str>, root: Option<String>) -> Result<()> {
+        let client = match self.connect.as_ref() {
+            Some(connect) => connect(name)?,
+            None => return Err(Error::Application("cannot switch the profile".to_owned())),
+        };
 
-                    needs_refresh = true;
-                }
-                KeyCode::Esc | KeyCode::Char('q') => self.quit_requested = true,
-                _ => (),
-            }
+        self.remember_location();
+        self.saved_roots.insert(self.profile.clone(), self.root());
+        let name = name.map(str::to_owned);
+        let mut root = root
+            .or(self.saved_roots.get(&name).cloned())
+            .or(name
+                .as_ref()
+                .and_then(|n| self.config.profiles.get(n))
+                .and_then(|p| p.root.clone()))
+            .unwrap_or(self.root());
+        if !root.ends_with('/') {
+            root.push('/');
         }
 
-        if needs_refresh {
-            if self.mode == Mode::Navigation {
-                self.update_selected_secret(FromCache::Yes)?;
-            }
+        self.client = client;
+        self.undo.clear();
+        self.preview_metadata = None;
+        self.token_expiry = TokenExpiry::lookup(&mut self.client);
+        self.state_key = match name.as_ref() {
+            Some(name) => name.clone(),
+            None => self.startup_key.clone().unwrap_or_default(),
+        };
+        self.profile = name;
+        self.filter = None;
+        self.invalidate_search_index();
+        self.root_len = VaultPath::decode(&root).entries.len();
+        let location = self
+            .state
+            .lock()
+            .last_location(&self.state_key, &root)
+            .unwrap_or(Location {
+                path: root,
+                selected: None,
+            });
+        self.restore_location(&location)?;
+        self.print()?;
+        self.print_info(&format!(
+            "switched to the profile '{}'",
+            profile_name(self.profile.as_deref())
+        ))
+    }
+
+    fn handle_typing_bookmark_name(&mut self) -> Result<()> {
+        let path = match self.current_list.is_empty() {
+            true => self.path.join(),
+            false => self.get_selected_source(),
+        };
+        let default = path
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        self.print_info(&format!("bookmark '{}' as: ", path))?;
+        execute!(stdout(), Print(" "))?;
 
+        let name = match self.read_line(&default)? {
+            Some(name) => name,
+            None => return self.cancel_typing(),
+        };
+        self.mode = Mode::Navigation;
+        if name.is_empty() {
             self.print()?;
-            self.displayed_message = None;
+            return Err(Error::Application(
+                "the bookmark name must not be empty".to_owned(),
+            ));
         }
+        let mut state = self.state.lock();
+        state
+            .profile(&self.state_key)
+            .bookmarks
+            .insert(name.clone(), path.clone());
+        state.save()?;
+        drop(state);
 
-        Ok(())
+        self.print()?;
+        self.print_info(&format!("bookmarked '{}' as '{}'", path, name))
     }
 
-    fn handle_typing_key(&mut self, edit_mode: EditMode) -> Result<()> {
-        let key = read_line()?;
-        if key.is_empty() {
+    fn open_jump_picker(&mut self) -> Result<()> {
+        let mut state = self.state.lock();
+        let profile = state.profile(&self.state_key);
+        self.jump_targets = profile
+            .bookmarks
+            .iter()
+            .map(|(name, path)| JumpTarget::Bookmark {
+                name: name.clone(),
+                path: path.clone(),
+            })
+            .chain(profile.recent.iter().cloned().map(JumpTarget::Recent))
+            .collect();
+        drop(state);
+        if self.jump_targets.is_empty() {
             self.mode = Mode::Navigation;
-            self.buffered_key.clear();
-            self.selected_item = self.previous_selected_item;
-
-            return Err(Error::Application("the key must not be empty".to_owned()));
+            self.print()?;
+            return Err(Error::Application(
+                "no bookmark or recent path yet, press [B] to bookmark the selection".to_owned(),
+            ));
         }
 
-        if key.ends_with('/') {
-            self.mode = Mode::Navigation;
-            self.buffered_key.clear();
-            self.selected_item = self.previous_selected_item;
+        let items = self
+            .jump_targets
+            .iter()
+            .map(|target| match target {
+                JumpTarget::Bookmark { name, path } => {
+                    (path.clone(), Some(format!("bookmark '{}'", name)))
+                }
+                JumpTarget::Recent(path) => (path.clone(), Some("recent".to_owned())),
+            })
+            .collect();
+        let selected = self.jump_picker.as_ref().map_or(0, |p| p.selected());
+        self.jump_picker = Some(Picker::new(
+            "jump to a bookmark or a recent path".to_owned(),
+            items,
+            selected,
+        ));
+        self.mode = Mode::PickingJump;
+        self.print()?;
+        self.displayed_message = None;
+        self.print_info("[Enter] jump    [D] remove the bookmark    [Esc] cancel")
+    }
 
-            return Err(Error::Application(
-                "to create a directory, please specify the key name, e.g. directory/keyName"
-                    .to_owned(),
-            ));
+    fn handle_picking_jump(&mut self) -> Result<()> {
+        let code = match self.read_event()? {
+            Event::Key(event) if event.kind == KeyEventKind::Press => event.code,
+            _ => return Ok(()),
+        };
+        let picker = match self.jump_picker.as_mut() {
+            Some(picker) => picker,
+            None => {
+                self.mode = Mode::Navigation;
+                return Ok(());
+            }
+        };
+
+        if code == KeyCode::Char('d') {
+            if let Some(JumpTarget::Bookmark { name, .. }) =
+                self.jump_targets.get(picker.selected())
+            {
+                let mut state = self.state.lock();
+                state.profile(&self.state_key).bookmarks.remove(name);
+                state.save()?;
+            }
+            return self.open_jump_picker();
         }
 
-        match edit_mode {
-            EditMode::Insert => {
-                self.buffered_key = key;
-                self.mode = Mode::TypingSecret(EditMode::Insert);
-                self.print()
+        match picker.handle_key(code) {
+            PickerAction::None => self.print(),
+            PickerAction::Close => {
+                self.jump_picker = None;
+                self.mode = Mode::Navigation;
+                self.print()?;
+                self.print_controls()
             }
-            EditMode::Update => {
+            PickerAction::Select(i) => {
+                self.jump_picker = None;
                 self.mode = Mode::Navigation;
-                self.rename_key(&key)
+                let path = match &self.jump_targets[i] {
+                    JumpTarget::Bookmark { path, .. } | JumpTarget::Recent(path) => path.clone(),
+                };
+                self.jump_to(&path)?;
+                self.print()?;
+                self.print_info(&format!("jumped to '{}'", path))
             }
         }
     }
 
-    fn handle_typing_secret(&mut self, secret_type: EditMode) -> Result<()> {
-        let secret = read_line()?;
+    fn handle_typing_grep_pattern(&mut self) -> Result<()> {
+        let directory = self.path.join();
+        self.print_info(&format!(
+            "search the values below '{}' for (regex): ",
+            directory
+        ))?;
+        execute!(stdout(), Print(" "))?;
+
+        let pattern = match self.read_line("")? {
+            Some(pattern) => pattern,
+            None => return self.cancel_typing(),
+        };
         self.mode = Mode::Navigation;
-        let key = match secret_type {
-            EditMode::Insert => self.buffered_key.clone(),
-            EditMode::Update => self.current_list[self.selected_item].name.clone(),
+        if pattern.is_empty() {
+            return Err(Error::Application("the regex must not be empty".to_owned()));
+        }
+        let regex = Regex::new(&pattern)?;
+
+        let report = grep_tree(
+            &mut self.client,
+            &directory,
+            &regex,
+            DEFAULT_CONCURRENCY,
+            |done, total| {
+                let _ = print_message(
+                    theme::get()
+                        .info
+                        .apply(format!(" reading {}/{} ", done, total)),
+                );
+            },
+        )?;
+        self.displayed_message = None;
+
+        // only the names of the matching fields are shown, never the values
+        let lines = report
+            .matches
+            .iter()
+            .map(|m| {
+                let names: Vec<&str> = m.fields.iter().map(|(name, _)| name.as_str()).collect();
+                (
+                    format!("{}{}", directory, m.key),
+                    Some(format!("({})", names.join(", "))),
+                )
+            })
+            .chain(
+                report
+                    .failed
+                    .iter()
+                    .map(|(path, err)| (path.clone(), Some(err.to_string()))),
+            )
+            .collect();
+        self.list_view = Some(ListView::new(
+            format!(
+                "'{}' in {} ({} of {} secrets match)",
+                pattern,
+                directory,
+                report.matches.len(),
+                report.scanned
+            ),
+            lines,
+        ));
+        self.mode = Mode::ShowingReport;
+        self.print()?;
+        self.print_info(&format!(
+            "{} secrets match{}    scroll with arrows or JK    press any other key to continue",
+            report.matches.len(),
+            match report.failed.len() {
+                0 => String::new(),
+                n => format!(", {} could not be read", n),
+            }
+        ))
+    }
+
+    fn handle_showing_diff(&mut self) -> Result<()> {
+        let code = match self.read_event()? {
+            Event::Key(event) if event.kind == KeyEventKind::Press => event.code,
+            _ => return Ok(()),
         };
-        let path = format!("{}{}", self.path.join(), key);
 
-        self.client.write_secret(&path, &secret)?;
-        self.set_selected_item(&key, FromCache::No)?;
+        let keep_open = match self.diff_view.as_mut() {
+            Some(view) => view.handle_key(code),
+            None => false,
+        };
+        if keep_open {
+            return self.print();
+        }
+
+        self.diff_view = None;
+        self.mode = Mode::Navigation;
         self.print()?;
+        self.print_controls()
+    }
 
-        match secret_type {
-            EditMode::Insert => self.print_info(&format!(
-                "added new key to the vault {} -> {}",
-                path, secret
-            ))?,
-            EditMode::Update => {
-                self.print_info(&format!("updated the secret of {} -> {}", path, secret))?
-            }
+    /// List the next directory of the search index, refreshing the results from time to time.
+    /// Show the keys found by the search index since the last call.
+    fn receive_search_index(&mut self) -> Result<()> {
+        let index = match self.search_index.as_mut() {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        if !index.receive() {
+            return Ok(());
         }
 
-        self.buffered_key.clear();
+        let view = match self.search_view.as_mut() {
+            Some(view) if matches!(self.mode, Mode::Searching) => view,
+            _ => return Ok(()),
+        };
+        view.update_results(&index.keys);
+        self.print()?;
+        let controls = self.search_view.as_ref().unwrap().controls();
+        self.displayed_message = None;
+        self.print_info(controls)
+    }
 
-        Ok(())
+    fn handle_searching(&mut self) -> Result<()> {
+        if self.search_view.is_none() || self.search_index.is_none() {
+            self.mode = Mode::Navigation;
+            return Ok(());
+        }
+
+        let event = match self.read_event()? {
+            Event::Key(event) if event.kind == KeyEventKind::Press => event,
+            _ => return Ok(()),
+        };
+        let (view, index) = match (self.search_view.as_mut(), self.search_index.as_ref()) {
+            (Some(view), Some(index)) => (view, index),
+            _ => return Ok(()),
+        };
+        let (action, query_changed) = view.handle_key(event);
+        if query_changed {
+            view.update_results(&index.keys);
+        }
+
+        match action {
+            SearchAction::None => {
+                self.print()?;
+                let controls = self.search_view.as_ref().unwrap().controls();
+                self.displayed_message = None;
+                self.print_info(controls)
+            }
+            SearchAction::Close => {
+                self.search_view = None;
+                self.mode = Mode::Navigation;
+                self.print()?;
+                self.print_controls()
+            }
+            SearchAction::Open(key) => {
+                self.search_view = None;
+                self.mode = Mode::Navigation;
+                let path = self.root() + &key;
+                self.open_path(&path)?;
+                self.print()?;
+                self.print_info(&format!("opened '{}'", path))
+            }
+        }
     }
 
     fn handle_deleting_key(&mut self) -> Result<()> {
@@ -595,18 +3149,28 @@ impl<H: HttpClient> Vaultwalker<H> {
         ))?;
         execute!(stdout(), Print(" "))?;
 
-        let answer = read_line()?;
-        // because the user presses on new line, we need to reset it
+        let answer = match self.read_line("")? {
+            Some(answer) => answer,
+            None => return self.cancel_typing(),
+        };
         self.print()?;
         self.mode = Mode::Navigation;
 
         if answer == "yes" {
             let mut path = self.path.join();
             path.push_str(&self.current_list[self.selected_item].name);
+            let before = read_state(&mut self.client, &path);
             self.client.delete_secret(&path)?;
+            let note = self.undo.record(before.map(|before| {
+                Change::new(
+                    format!("deletion of '{}'", path),
+                    vec![(path.clone(), before, None)],
+                )
+            }));
+            self.invalidate_search_index();
 
             // if this is the only item in the list, we need to climb up
-            while self.current_list.len() == 1 {
+            while self.unfiltered_list.len() == 1 {
                 let last = self.path.entries.pop().unwrap();
                 self.set_selected_item(&last.name, FromCache::Yes)?;
                 self.scroll = 0;
@@ -619,7 +3183,7 @@ impl<H: HttpClient> Vaultwalker<H> {
 
             self.refresh_all()?;
             self.print()?;
-            self.print_info(&format!("deleted the key '{}'", path))
+            self.print_info(&format!("deleted the key '{}'{}", path, note))
         } else {
             self.print()?;
             self.print_error(Error::Application(format!(
@@ -631,11 +3195,55 @@ impl<H: HttpClient> Vaultwalker<H> {
 
     fn input_loop(&mut self) -> Result<()> {
         loop {
+            if let Err(err) = self.receive_search_index() {
+                self.print_error(err)?;
+            }
+
+            // the other modes may show a selection that is not saved yet, e.g. a new key
+            if matches!(self.mode, Mode::Navigation) {
+                self.remember_location();
+            }
+
+            // wait for the next event until the copied secret must be removed, or until the
+            // search index may have found more keys
+            let deadline = self.clipboard.clear_deadline();
+            let mut wait = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            if self.search_index.as_ref().is_some_and(|i| !i.is_done()) {
+                wait = Some(wait.map_or(INDEX_REFRESH, |w| w.min(INDEX_REFRESH)));
+            }
+            if let Some(wait) = wait {
+                if !poll(wait)? {
+                    if deadline.is_some_and(|d| Instant::now() >= d) {
+                        self.clipboard.clear()?;
+                        if matches!(self.mode, Mode::Navigation) {
+                            self.print_info("secret removed from the clipboard")?;
+                        }
+                    }
+                    continue;
+                }
+            }
             let err = match self.mode {
                 Mode::Navigation => self.handle_navigation(),
                 Mode::TypingKey(em) => self.handle_typing_key(em),
                 Mode::TypingSecret(em) => self.handle_typing_secret(em),
+                Mode::TypingDestination(kind) => self.handle_typing_destination(kind),
+                Mode::ConfirmingTransfer => self.handle_confirming_transfer(),
                 Mode::DeletingKey => self.handle_deleting_key(),
+                Mode::ConfirmingDirectoryDelete => self.handle_confirming_directory_delete(),
+                Mode::TypingDirectoryDelete(mode) => self.handle_typing_directory_delete(mode),
+                Mode::ShowingReport => self.handle_showing_report(),
+                Mode::TypingComparedPath => self.handle_typing_compared_path(),
+                Mode::ShowingDiff => self.handle_showing_diff(),
+                Mode::Searching => self.handle_searching(),
+                Mode::TypingGrepPattern => self.handle_typing_grep_pattern(),
+                Mode::Filtering => self.handle_filtering(),
+                Mode::PickingProfile => self.handle_picking_profile(),
+                Mode::TypingProfileRoot => self.handle_typing_profile_root(),
+                Mode::TypingBookmarkName => self.handle_typing_bookmark_name(),
+                Mode::PickingJump => self.handle_picking_jump(),
+                Mode::ShowingDetails => self.handle_showing_details(),
+                Mode::TypingExportFile => self.handle_typing_export_file(),
+                Mode::ConfirmingUndo => self.handle_confirming_undo(),
             };
 
             if let Err(err) = err {
@@ -643,10 +3251,17 @@ impl<H: HttpClient> Vaultwalker<H> {
                 self.print_error(err)?;
             }
 
+            // a secret copied just before quitting is left to be pasted elsewhere
             if self.quit_requested {
                 disable_raw_mode()?;
-                execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
-                return Ok(());
+                execute!(
+                    stdout(),
+                    DisableBracketedPaste,
+                    DisableMouseCapture,
+                    LeaveAlternateScreen,
+                    cursor::Show
+                )?;
+                return self.state.lock().save();
             }
         }
     }
@@ -657,8 +3272,29 @@ struct Args {
     #[options(help_flag)]
     help: bool,
 
-    #[options(free, required, help = "Path to the root of the vault")]
-    root_path: String,
+    #[options(
+        free,
+        help = "Path to the root of the vault, defaults to the root of the profile"
+    )]
+    root_path: Option<String>,
+
+    #[options(help = "Profile of the config file to use", short = "P")]
+    profile: Option<String>,
+
+    #[options(help = "Start at this bookmark of the profile", meta = "NAME")]
+    bookmark: Option<String>,
+
+    #[options(
+        help = "Show the values of the secrets, they are masked by default",
+        no_short
+    )]
+    show_secrets: bool,
+
+    #[options(help = "Print the path of the key chosen with Enter", no_short)]
+    pick: bool,
+
+    #[options(help = "Print the value of the key chosen with Enter", no_short)]
+    pick_value: bool,
 
     #[options(help = "URL of the vault server, defaults to $VAULT_ADDR", short = "H")]
     host: Option<String>,
@@ -668,59 +3304,229 @@ struct Args {
 }
 
 struct ParsedArgs {
-    host: String,
-    token: String,
+    connection: Connection,
     root: String,
+    /// The root path when it was given without a trailing `/`, it may be a key
+    key: Option<String>,
+    /// Path opened at launch instead of the root
+    start: Option<String>,
+    show_secrets: bool,
+    pick: Option<Pick>,
 }
 
-fn run(host: String, token: String, root: String) -> Result<()> {
-    if root == "mock/" {
-        let mock_client = MockClient {};
-        let mut vaultwalker = Vaultwalker::new(mock_client, root)?;
-        vaultwalker.setup()?;
-        vaultwalker.input_loop()
+/// Browse the vault, returns what was chosen in the pick mode.
+fn run(args: ParsedArgs, config: Config, state: SharedState) -> Result<Option<String>> {
+    if commands::is_mock(&args.root) {
+        // every profile gets its own demo vault
+        let client = VaultClient::new(MockClient::new());
+        browse(
+            client,
+            args,
+            config,
+            state,
+            Box::new(|_| Ok(VaultClient::new(MockClient::new()))),
+        )
     } else {
-        let http_client = UreqClient::new(&host, &token);
-        let mut vaultwalker = Vaultwalker::new(http_client, root)?;
-        vaultwalker.setup()?;
-        vaultwalker.input_loop()
+        let client = args.connection.connect()?;
+        let profiles = config.clone();
+        let startup = args.connection.clone();
+        browse(
+            client,
+            args,
+            config,
+            state,
+            // the flags given at startup only apply to the connection it made
+            Box::new(move |name| match name {
+                Some(name) if startup.profile.as_deref() != Some(name) => {
+                    Connection::resolve(&profiles, Some(name), None, None)?.connect()
+                }
+                _ => startup.connect(),
+            }),
+        )
+    }
+}
+
+/// Name of a profile in the picker and the messages.
+fn profile_name(name: Option<&str>) -> &str {
+    name.unwrap_or(STARTUP_PROFILE)
+}
+
+fn browse<H: HttpClient + Clone + Send + 'static>(
+    mut client: VaultClient<H>,
+    args: ParsedArgs,
+    config: Config,
+    state: SharedState,
+    connect: Connector<H>,
+) -> Result<Option<String>> {
+    let ParsedArgs {
+        connection,
+        mut root,
+        key,
+        mut start,
+        show_secrets,
+        pick,
+    } = args;
+    if let Some(directory) = key.as_deref().and_then(|k| key_directory(&mut client, k)) {
+        root = directory;
+        start = key;
+    }
+
+    let mut vaultwalker = Vaultwalker::new(client, root)?
+        .with_profiles(config, connection.profile.clone(), connect)
+        .with_state(state, connection.state_key())
+        .with_reveal(show_secrets)
+        .with_pick(pick);
+    vaultwalker.setup()?;
+    if let Some(start) = start {
+        let res = vaultwalker.jump_to(&start);
+        vaultwalker.print()?;
+        if let Err(err) = res {
+            vaultwalker.print_error(err)?;
+        }
     }
+    vaultwalker.input_loop()?;
+
+    Ok(vaultwalker.picked.take())
+}
+
+/// The directory of `path` when it is a key and not a directory.
+fn key_directory<H: HttpClient>(client: &mut VaultClient<H>, path: &str) -> Option<String> {
+    let (directory, name) = path.rsplit_once('/')?;
+    let directory = format!("{}/", directory);
+    // without the right to list the directory, the path is opened as a directory like before
+    let keys = client.list_secrets(&directory, FromCache::No).ok()?.keys;
+    let is_key = keys.iter().any(|k| k == name) && !keys.contains(&format!("{}/", name));
+
+    is_key.then_some(directory)
 }
 
-fn parse_args(opts: Args) -> Result<ParsedArgs> {
-    let mut root = opts.root_path;
+/// Merge the flags with the config file, see `Connection::resolve` for the precedence.
+///
+/// The root path given on the command line takes precedence over the root of the profile, and
+/// a bookmark outside of the root opens its mount. The root path may also be a key, which is only
+/// known once connected.
+fn parse_args(opts: Args, config: &Config, state: &State) -> Result<ParsedArgs> {
+    let connection = Connection::resolve(config, opts.profile.as_deref(), opts.host, opts.token)?;
+    let start = match opts.bookmark {
+        Some(name) => Some(state.bookmark(&connection.state_key(), &name)?),
+        None => None,
+    };
+    let key = opts.root_path.clone().filter(|path| !path.ends_with('/'));
+    let pick = match (opts.pick, opts.pick_value) {
+        (_, true) => Some(Pick::Value),
+        (true, false) => Some(Pick::Path),
+        (false, false) => None,
+    };
+    let mut root = opts
+        .root_path
+        .or_else(|| connection.root.clone())
+        .or_else(|| {
+            start
+                .as_ref()
+                .and_then(|path| path.split_inclusive('/').next().map(String::from))
+        })
+        .ok_or(Error::Application(
+            "please specify the root path, or set the root of the profile in the config file"
+                .to_owned(),
+        ))?;
     if !root.ends_with('/') {
         root += "/";
     }
 
-    let host = opts.host.or_else(|| std::env::var("VAULT_ADDR").ok()).ok_or(Error::Application(
-        "please specify the vault server URL with -H option or set the VAULT_ADDR environment variable".to_owned(),
-    ))?;
-    let token = opts.token.or_else(|| read_to_string(home_dir().unwrap().join(".vault-token")).ok()).ok_or(Error::Application(
-        "cannot find ~/.vault-token file, please specify the token with -t option or use the 'vault login' command to create it".to_owned()
-    ))?;
+    Ok(ParsedArgs {
+        connection,
+        root,
+        key,
+        start,
+        show_secrets: opts.show_secrets,
+        pick,
+    })
+}
+
+/// Same as `Options::parse_args_default_or_exit`, with the list of commands in the main usage.
+fn parse_args_or_exit<T: Options>(args: &[String], usage: &str) -> T {
+    let opts = T::parse_args_default(args).unwrap_or_else(|err| {
+        eprintln!("{}: {}", usage, err);
+        std::process::exit(2);
+    });
+
+    if opts.help_requested() {
+        eprintln!("Usage: {} [OPTIONS]", usage);
+        eprintln!();
+        eprintln!("{}", opts.self_usage());
+        if T::command_list().is_none() {
+            eprintln!();
+            eprintln!("Available commands:");
+            eprintln!("{}", Command::usage());
+        }
+        std::process::exit(0);
+    }
 
-    Ok(ParsedArgs { host, token, root })
+    opts
 }
 
 fn main() {
-    let ParsedArgs { host, token, root } = parse_args(Args::parse_args_default_or_exit())
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && Command::command_usage(&args[1]).is_some() {
+        let command = parse_args_or_exit::<Command>(&args[1..], &format!("vw {}", args[1]));
+        commands::run(command).unwrap_or_else(|err: Error| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+        return;
+    }
+
+    let opts = parse_args_or_exit(&args[1..], "vw");
+    let (parsed, config, state) = Config::load()
+        .and_then(|config| {
+            let state = State::load()?;
+            Ok((parse_args(opts, &config, &state)?, config, state))
+        })
         .unwrap_or_else(|err: Error| {
             eprintln!("{}", err);
             std::process::exit(2);
         });
+    theme::set(match theme::colors_disabled() {
+        true => Theme::no_color(),
+        // the theme was checked when the config file was loaded
+        false => config.theme().unwrap_or_else(|_| Theme::dark()),
+    });
 
-    ctrlc::set_handler(|| {
+    // the raw mode turns Ctrl-C into a key press, even while a line is typed, so this only
+    // handles a SIGINT sent by another process or received before or after the browser runs
+    let state = SharedState::new(state);
+    let interrupted_state = state.clone();
+    ctrlc::set_handler(move || {
         disable_raw_mode().unwrap();
-        execute!(stdout(), LeaveAlternateScreen, cursor::Show).unwrap();
+        execute!(
+            stdout(),
+            DisableBracketedPaste,
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            cursor::Show
+        )
+        .unwrap();
+        if let Err(err) = interrupted_state.lock().save() {
+            eprintln!("{}", err);
+        }
         std::process::exit(1);
     })
     .expect("Error setting Ctrl-C handler");
 
-    run(host, token, root).unwrap_or_else(|err: Error| {
+    // the output is redirected before anything is drawn
+    let output = parsed.pick.map(|_| {
+        PickOutput::new().unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        })
+    });
+
+    let picked = run(parsed, config, state).unwrap_or_else(|err: Error| {
         disable_raw_mode().unwrap();
         execute!(
             stdout(),
+            DisableBracketedPaste,
+            DisableMouseCapture,
             LeaveAlternateScreen,
             cursor::Show,
             MoveDown(20000),
@@ -729,6 +3535,16 @@ fn main() {
         .unwrap();
         std::process::exit(1);
     });
+    if let Some(output) = output {
+        match picked {
+            Some(picked) => output.print(&picked).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }),
+            // nothing was chosen
+            None => std::process::exit(1),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -762,34 +3578,169 @@ mod tests {
         assert_eq!(path.entries[1].name, "dir");
         assert!(!path.entries[1].is_dir);
 
-        assert_eq!(path.len(), 9);
+        assert_eq!(path.len(), 8);
         assert_eq!(path.join(), "test/dir");
-    }
 
-    #[test]
-    fn test_shorten_string() {
-        assert_eq!(shorten_string("test", 10), "test");
-        assert_eq!(shorten_string("test", 3), "tes...");
+        // the names are escaped on the screen only
+        let path = VaultPath::decode("clé/\x1b[2J/");
+        assert_eq!(path.join(), "clé/\x1b[2J/");
+        assert_eq!(path.to_string(), "clé/\\u{1b}[2J/");
+        assert_eq!(path.len(), 14);
     }
 
     #[test]
     fn test_parse_args() {
         let args = Args {
             help: false,
-            root_path: "mock".to_owned(),
+            root_path: Some("mock".to_owned()),
+            profile: None,
+            bookmark: None,
+            show_secrets: false,
+            pick: false,
+            pick_value: true,
             host: Some("http://localhost:8200".to_owned()),
             token: Some("test_token".to_owned()),
         };
-        let parsed = parse_args(args).unwrap();
+        let parsed = parse_args(args, &Config::default(), &State::default()).unwrap();
 
-        assert_eq!(parsed.host, "http://localhost:8200");
-        assert_eq!(parsed.token, "test_token");
+        assert_eq!(parsed.connection.address, "http://localhost:8200");
+        assert_eq!(
+            parsed.connection.credentials,
+            config::Credentials::Token("test_token".to_owned())
+        );
         assert_eq!(parsed.root, "mock/");
+        assert_eq!(parsed.key.as_deref(), Some("mock"));
+        assert_eq!(parsed.pick, Some(Pick::Value));
+
+        // a path is a key only if it is listed as one
+        let mut client = VaultClient::new(MockClient::new());
+        assert_eq!(
+            key_directory(&mut client, "mock/key3").as_deref(),
+            Some("mock/")
+        );
+        assert_eq!(key_directory(&mut client, "mock/key9"), None);
+        assert_eq!(key_directory(&mut client, "mock/missing"), None);
+        assert_eq!(key_directory(&mut client, "mock"), None);
+
+        // the root comes from the profile when it is not given
+        let config = Config::parse(
+            "[profiles.dev]\naddress = 'http://dev:8200'\nroot = 'secret/dev'\nauth = { method = 'token', token = 'dev_token' }",
+        )
+        .unwrap();
+        let mut state = State::default();
+        state
+            .profile("dev")
+            .bookmarks
+            .insert("db".to_owned(), "other/db/".to_owned());
+        let args = Args {
+            help: false,
+            root_path: None,
+            profile: Some("dev".to_owned()),
+            bookmark: Some("db".to_owned()),
+            show_secrets: false,
+            pick: false,
+            pick_value: false,
+            host: None,
+            token: None,
+        };
+        let parsed = parse_args(args, &config, &state).unwrap();
+        assert_eq!(parsed.connection.address, "http://dev:8200");
+        assert_eq!(parsed.root, "secret/dev/");
+        assert_eq!(parsed.start.as_deref(), Some("other/db/"));
+        assert_eq!(parsed.key, None);
+        assert_eq!(parsed.pick, None);
+    }
+
+    #[test]
+    fn test_secret_details() {
+        let mut client = VaultClient::new(MockClient::new());
+        client.write_secret("mockv2/app/key", "new value").unwrap();
+        let mut details = client.get_secret_details("mockv2/app/key").unwrap();
+        assert_eq!(details.metadata.as_ref().unwrap().current_version, 2);
+
+        details.warnings.push("deprecated".to_owned());
+        let lines = detail_lines(&details, false);
+        assert_eq!(lines[0].0, "secret: ****** (9 characters)");
+        assert!(lines.contains(&("version: 2".to_owned(), None)));
+        assert!(lines.contains(&("max versions: unlimited".to_owned(), None)));
+        assert!(lines.contains(&("metadata owner: mock".to_owned(), None)));
+        assert_eq!(
+            lines.last(),
+            Some(&("warning:".to_owned(), Some("deprecated".to_owned())))
+        );
+        assert_eq!(
+            detail_lines(&details, true)[0].0,
+            "secret: new value (9 characters)"
+        );
+
+        // KV v1 has no metadata
+        let details = client.get_secret_details("mock/key2").unwrap();
+        assert!(details.metadata.is_none());
+        assert!(detail_lines(&details, false).contains(&("KV v1, no versions".to_owned(), None)));
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let now = Instant::now();
+        let at = |secs| TokenExpiry::At(now + Duration::from_secs(secs));
+        assert_eq!(TokenExpiry::Unknown.describe(now), "?");
+        assert_eq!(TokenExpiry::Never.describe(now), "no expiry");
+        assert_eq!(at(0).describe(now), "expired");
+        assert_eq!(at(42).describe(now), "42s");
+        assert_eq!(at(125).describe(now), "2m05s");
+        assert_eq!(at(7500).describe(now), "2h05m");
+        assert_eq!(at(3 * 86400 + 7200).describe(now), "3d02h");
+        assert_eq!(at(10).describe(now + Duration::from_secs(20)), "expired");
+
+        let mut client = VaultClient::new(MockClient::new());
+        assert_eq!(TokenExpiry::lookup(&mut client), TokenExpiry::Never);
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut vw =
+            Vaultwalker::new(VaultClient::new(MockClient::new()), "mock/".to_owned()).unwrap();
+        vw.update_list(FromCache::No).unwrap();
+
+        vw.toggle_selection(1);
+        vw.select_range(3);
+        vw.toggle_selection(8);
+        assert_eq!(vw.selected_entries(), vec!["key2", "key3", "key4", "key9/"]);
+        assert_eq!(vw.marker(&vw.current_list[8]), '+');
+        assert_eq!(vw.marker(&vw.current_list[0]), ' ');
+        // the range starts at the last toggled entry
+        vw.select_range(10);
+        assert_eq!(vw.selection.len(), 6);
+        vw.toggle_selection(9);
+        assert_eq!(
+            vw.selected_entries(),
+            vec!["key2", "key3", "key4", "key9/", "key11"]
+        );
+
+        // the selection follows the listing and is cleared in another directory
+        vw.client.delete_secret("mock/key3").unwrap();
+        vw.update_list(FromCache::No).unwrap();
+        assert_eq!(
+            vw.selected_entries(),
+            vec!["key2", "key4", "key9/", "key11"]
+        );
+
+        // the bulk actions ignore the entries hidden by the filter
+        vw.filter = Some(ListFilter::new("key1").unwrap());
+        vw.apply_filter();
+        assert_eq!(vw.selected_entries(), vec!["key11"]);
+        vw.filter = None;
+        vw.apply_filter();
+
+        vw.path = VaultPath::decode("mock/key9/");
+        vw.update_list(FromCache::No).unwrap();
+        assert!(vw.selection.is_empty());
     }
 
     #[test]
     fn test_vaultwalker() {
-        let mut vw = Vaultwalker::new(MockClient {}, "mock/".to_owned()).unwrap();
+        let mut vw =
+            Vaultwalker::new(VaultClient::new(MockClient::new()), "mock/".to_owned()).unwrap();
 
         // test the initial state
         assert!(vw.update_list(FromCache::No).is_ok());
@@ -814,6 +3765,13 @@ mod tests {
         assert!(vw.selected_secret.is_some());
         let secret = vw.selected_secret.as_ref().unwrap();
         assert_eq!(<&VaultSecret as Into<String>>::into(secret), "value");
+        // the values are masked until revealed
+        assert_eq!(
+            vw.selected_line_for_current_mode(&vw.current_list[0], 80)
+                .unwrap(),
+            "> key1/ -> \u{1b}[1m******\u{1b}[0m"
+        );
+        vw.reveal = true;
         assert_eq!(
             vw.selected_line_for_current_mode(&vw.current_list[0], 80)
                 .unwrap(),
@@ -831,4 +3789,133 @@ mod tests {
             "the key 'key3' already exists"
         );
     }
+
+    #[test]
+    fn test_navigation_keys() {
+        let mut vw =
+            Vaultwalker::new(VaultClient::new(MockClient::new()), "mock/".to_owned()).unwrap();
+        vw.update_list(FromCache::No).unwrap();
+        let press = |vw: &mut Vaultwalker<MockClient>, code, modifiers| {
+            vw.handle_key(KeyEvent::new(code, modifiers), 4)
+        };
+        let none = KeyModifiers::NONE;
+
+        // a count repeats a move, or selects an entry
+        assert!(!press(&mut vw, KeyCode::Char('5'), none).unwrap());
+        assert!(press(&mut vw, KeyCode::Char('j'), none).unwrap());
+        assert_eq!(vw.selected_item, 5);
+        assert!(vw.count.is_none());
+        press(&mut vw, KeyCode::Char('3'), none).unwrap();
+        press(&mut vw, KeyCode::Char('G'), KeyModifiers::SHIFT).unwrap();
+        assert_eq!(vw.selected_item, 2);
+        press(&mut vw, KeyCode::Char('G'), KeyModifiers::SHIFT).unwrap();
+        assert_eq!(vw.selected_item, 14);
+        press(&mut vw, KeyCode::Char('g'), none).unwrap();
+        assert_eq!(vw.selected_item, 0);
+
+        // half pages and pages of 4 rows, the moves stop at the last entry
+        press(&mut vw, KeyCode::Char('d'), KeyModifiers::CONTROL).unwrap();
+        press(&mut vw, KeyCode::Char('d'), KeyModifiers::CONTROL).unwrap();
+        assert_eq!(vw.selected_item, 4);
+        press(&mut vw, KeyCode::Char('u'), KeyModifiers::CONTROL).unwrap();
+        assert_eq!(vw.selected_item, 2);
+        press(&mut vw, KeyCode::PageDown, none).unwrap();
+        assert_eq!(vw.selected_item, 6);
+        press(&mut vw, KeyCode::Char('2'), none).unwrap();
+        press(&mut vw, KeyCode::PageDown, none).unwrap();
+        assert_eq!(vw.selected_item, 14);
+        press(&mut vw, KeyCode::PageUp, none).unwrap();
+        assert_eq!(vw.selected_item, 10);
+
+        // Enter opens a directory and Backspace goes back to it
+        press(&mut vw, KeyCode::Home, none).unwrap();
+        press(&mut vw, KeyCode::Enter, none).unwrap();
+        assert_eq!(vw.path.join(), "mock/key1/");
+        press(&mut vw, KeyCode::Backspace, none).unwrap();
+        assert_eq!(vw.path.join(), "mock/");
+        assert_eq!(vw.get_selected_path(), "mock/key1");
+    }
+
+    #[test]
+    fn test_pick_with_enter() {
+        let mut vw = Vaultwalker::new(VaultClient::new(MockClient::new()), "mock/".to_owned())
+            .unwrap()
+            .with_pick(Some(Pick::Path));
+        vw.update_list(FromCache::No).unwrap();
+        vw.set_selected_item("key2", FromCache::No).unwrap();
+
+        // the other keys of `open` do not pick
+        vw.handle_key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE), 4)
+            .unwrap();
+        assert!(vw.picked.is_none());
+        vw.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 4)
+            .unwrap();
+        assert_eq!(vw.picked.as_deref(), Some("mock/key2"));
+        assert!(vw.quit_requested);
+    }
+
+    #[test]
+    fn test_jump_to() {
+        // jumping outside of the root moves the root up
+        let mut vw =
+            Vaultwalker::new(VaultClient::new(MockClient::new()), "mock/key9/".to_owned()).unwrap();
+        vw.jump_to("mock/key15/deep/key").unwrap();
+        assert_eq!(vw.root(), "mock/");
+        assert_eq!(vw.get_selected_path(), "mock/key15/deep/key");
+        assert!(vw.jump_to("mock/gone/").is_err());
+        assert_eq!(vw.path.join(), "mock/");
+        assert_eq!(vw.state.lock().profile("").recent, vec!["mock/key15/deep/"]);
+    }
+
+    #[test]
+    fn test_restore_location() {
+        let mut vw =
+            Vaultwalker::new(VaultClient::new(MockClient::new()), "mock/".to_owned()).unwrap();
+        vw.update_list(FromCache::No).unwrap();
+
+        // the last location is restored, or its closest existing directory
+        vw.remember_location();
+        let last = vw.state.lock().last_location("", "mock/").unwrap();
+        assert_eq!(last.path, "mock/");
+        assert_eq!(last.selected.as_deref(), Some("key1"));
+        vw.restore_location(&Location {
+            path: "mock/key15/deep/".to_owned(),
+            selected: Some("key".to_owned()),
+        })
+        .unwrap();
+        assert_eq!(vw.get_selected_path(), "mock/key15/deep/key");
+        vw.restore_location(&Location {
+            path: "mock/key15/gone/".to_owned(),
+            selected: Some("key".to_owned()),
+        })
+        .unwrap();
+        assert_eq!(vw.path.join(), "mock/key15/");
+        assert_eq!(vw.selected_item, 0);
+    }
+
+    #[test]
+    fn test_preview_metadata() {
+        let mut vw =
+            Vaultwalker::new(VaultClient::new(MockClient::new()), "mockv2/".to_owned()).unwrap();
+        vw.jump_to("mockv2/app/key").unwrap();
+        let lines = vw.preview_column(10, 40);
+        assert!(lines.iter().any(|line| line.contains("version: 1")));
+
+        // the metadata is read once per key, even when it cannot be read
+        vw.preview_metadata = Some((vw.get_selected_path(), vec!["cached".to_owned()]));
+        let lines = vw.preview_column(10, 40);
+        assert!(lines.iter().any(|line| line.contains("cached")));
+        vw.update_selected_secret(FromCache::No).unwrap();
+        assert!(vw.preview_metadata.is_none());
+
+        assert_eq!(
+            preview_metadata(Err(Error::NotFound("mockv2/gone".to_owned()))),
+            vec!["metadata: cannot be read"]
+        );
+        let mut client = VaultClient::new(MockClient::new());
+        assert_eq!(
+            preview_metadata(client.get_metadata("mock/key2", FromCache::Yes)),
+            vec!["metadata: n/a (KV v1)"]
+        );
+    }
 }
@@ -0,0 +1,49 @@
+use std::{fs::File, io::Write};
+
+use crate::error::Result;
+
+/// What is printed when a key is chosen with Enter.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Pick {
+    Path,
+    Value,
+}
+
+/// In the pick mode the browser is drawn on the terminal, so that stdout only gets the picked
+/// path or value, e.g. in `psql "$(vw --pick secret/db)"`.
+pub struct PickOutput(File);
+
+impl PickOutput {
+    /// Point stdout to the terminal, and keep the original stdout to print the result.
+    #[cfg(unix)]
+    pub fn new() -> Result<Self> {
+        use std::{
+            fs::OpenOptions,
+            io::stdout,
+            os::fd::{AsFd, AsRawFd},
+        };
+
+        let tty = OpenOptions::new().write(true).open("/dev/tty")?;
+        stdout().flush()?;
+        let original = stdout().as_fd().try_clone_to_owned()?;
+        // SAFETY: both file descriptors are open for the whole call
+        if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(Self(File::from(original)))
+    }
+
+    #[cfg(not(unix))]
+    pub fn new() -> Result<Self> {
+        Err(crate::error::Error::Application(
+            "the pick mode is only available on unix".to_owned(),
+        ))
+    }
+
+    pub fn print(mut self, picked: &str) -> Result<()> {
+        writeln!(self.0, "{}", picked)?;
+
+        Ok(())
+    }
+}
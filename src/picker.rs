@@ -0,0 +1,109 @@
+use std::io::stdout;
+
+use crossterm::{
+    cursor::MoveToNextLine,
+    event::KeyCode,
+    execute,
+    style::{ContentStyle, Print},
+};
+
+use crate::{
+    error::Result,
+    layout,
+    render::{self, escape, shorten},
+    theme,
+};
+
+pub enum PickerAction {
+    None,
+    Close,
+    Select(usize),
+}
+
+/// List of choices shown in place of the listing, e.g. to switch to another profile.
+pub struct Picker {
+    title: String,
+    /// Each choice, with an optional note dimmed after it
+    items: Vec<(String, Option<String>)>,
+    selected: usize,
+    scroll: usize,
+}
+
+impl Picker {
+    pub fn new(title: String, items: Vec<(String, Option<String>)>, selected: usize) -> Self {
+        Self {
+            title,
+            selected: selected.min(items.len().saturating_sub(1)),
+            items,
+            scroll: 0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Handle a key press, the keys specific to a picker must be handled before.
+    pub fn handle_key(&mut self, code: KeyCode) -> PickerAction {
+        match code {
+            KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.items.len() => {
+                self.selected += 1
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter if !self.items.is_empty() => return PickerAction::Select(self.selected),
+            KeyCode::Esc | KeyCode::Char('q') => return PickerAction::Close,
+            _ => (),
+        }
+
+        PickerAction::None
+    }
+
+    pub fn print(&mut self, width: usize, height: usize) -> Result<()> {
+        execute!(
+            stdout(),
+            Print(
+                theme::get()
+                    .title
+                    .apply(shorten(&escape(&self.title), width.saturating_sub(3)))
+            ),
+            MoveToNextLine(1)
+        )?;
+
+        let visible = height.saturating_sub(2);
+        self.scroll = layout::scroll(self.selected, self.scroll, self.items.len(), visible);
+
+        for (i, (item, note)) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(visible)
+        {
+            let line = format!(
+                "{} {}",
+                if i == self.selected { ">" } else { " " },
+                escape(item)
+            );
+            let note = note.as_deref().map(escape);
+            let note_len = note.as_ref().map_or(0, |n| render::width(n) + 2);
+            let line = shorten(&line, width.saturating_sub(note_len + 3).max(3));
+            execute!(
+                stdout(),
+                Print(if i == self.selected {
+                    theme::get().selected.apply(line)
+                } else {
+                    ContentStyle::new().apply(line)
+                })
+            )?;
+            if let Some(note) = note {
+                execute!(
+                    stdout(),
+                    Print(theme::get().dim.apply(format!("  {}", note)))
+                )?;
+            }
+            execute!(stdout(), MoveToNextLine(1))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns taken by `text` on the terminal, the styling escape sequences take none.
+pub fn width(text: &str) -> usize {
+    strip_styles(text).graphemes(true).map(grapheme_width).sum()
+}
+
+/// A grapheme is at most two columns wide, e.g. an emoji joined with ZWJ.
+fn grapheme_width(grapheme: &str) -> usize {
+    grapheme.width().min(2)
+}
+
+/// Remove the SGR sequences added by the styling, e.g. `\x1b[1m`.
+pub fn strip_styles(text: &str) -> Cow<'_, str> {
+    if !text.contains('\x1b') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            chars.next();
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    Cow::Owned(stripped)
+}
+
+/// Show the control characters as escapes, e.g. `\n` or `\u{1b}`, so that a key or a value
+/// cannot move the cursor or send escape sequences to the terminal.
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(char::is_control) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.extend(c.escape_unicode()),
+            c => escaped.push(c),
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+/// Keep the first `max_width` columns of `text` followed by `...` when it is longer, without
+/// splitting a character.
+pub fn shorten(text: &str, max_width: usize) -> String {
+    if width(text) <= max_width {
+        return text.to_owned();
+    }
+
+    let mut shortened = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        used += grapheme_width(grapheme);
+        if used > max_width {
+            break;
+        }
+        shortened.push_str(grapheme);
+    }
+    shortened.push_str("...");
+
+    shortened
+}
+
+/// Pad `styled` to `width` columns, or shorten `plain` when it does not fit.
+pub fn fit(plain: &str, styled: String, width: usize) -> String {
+    let plain_width = self::width(plain);
+    match plain_width > width {
+        true => {
+            let shortened = shorten(plain, width.saturating_sub(3));
+            // a wide character may leave one column
+            let padding = width.saturating_sub(self::width(&shortened));
+            format!("{}{}", shortened, " ".repeat(padding))
+        }
+        false => format!("{}{}", styled, " ".repeat(width - plain_width)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width("key"), 3);
+        assert_eq!(width("clé"), 3);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(width("日本"), 4);
+        assert_eq!(width("👨‍👩‍👧"), 2);
+        assert_eq!(width("\x1b[1mkey\x1b[0m"), 3);
+        assert_eq!(strip_styles("\x1b[38;5;9mred\x1b[39m"), "red");
+    }
+
+    #[test]
+    fn test_shorten() {
+        assert_eq!(shorten("test", 10), "test");
+        assert_eq!(shorten("test", 3), "tes...");
+        assert_eq!(shorten("clés", 3), "clé...");
+        assert_eq!(shorten("日本語", 3), "日...");
+        assert_eq!(shorten("🔑🔑", 1), "...");
+        assert_eq!(fit("日本語", "日本語".to_owned(), 6), "日本語");
+        assert_eq!(fit("日本語", "日本語".to_owned(), 5), "日...");
+        assert_eq!(fit("日本語x", "日本語x".to_owned(), 6), "日... ");
+        assert_eq!(
+            fit("ab", "\x1b[1mab\x1b[0m".to_owned(), 4),
+            "\x1b[1mab\x1b[0m  "
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("value"), "value");
+        assert_eq!(escape("line\nnext\t"), "line\\nnext\\t");
+        assert_eq!(escape("\x1b]52;c;aGk=\x07"), "\\u{1b}]52;c;aGk=\\u{7}");
+        assert_eq!(escape("\u{9b}2J"), "\\u{9b}2J");
+    }
+}
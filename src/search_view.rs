@@ -0,0 +1,142 @@
+use std::io::stdout;
+
+use crossterm::{
+    cursor::MoveToNextLine,
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::{Print, Stylize},
+};
+
+use crate::{
+    error::Result,
+    fuzzy::{fuzzy_filter, FuzzyMatch},
+    layout,
+    render::{self, escape},
+    theme,
+};
+
+/// Only the best results are kept, there is no point in scrolling through thousands of keys.
+const MAX_RESULTS: usize = 500;
+
+pub enum SearchAction {
+    None,
+    Close,
+    /// Open the key with this path, relative to the searched root
+    Open(String),
+}
+
+/// Fuzzy search of the keys below the root, shown in place of the listing.
+pub struct SearchView {
+    query: String,
+    results: Vec<(String, FuzzyMatch)>,
+    selected: usize,
+    scroll: usize,
+}
+
+impl SearchView {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            results: vec![],
+            selected: 0,
+            scroll: 0,
+        }
+    }
+
+    pub fn controls(&self) -> &'static str {
+        "type to search    select with arrows    [Enter] open the key    [Esc] cancel"
+    }
+
+    /// Match the query against the keys indexed so far.
+    pub fn update_results(&mut self, keys: &[String]) {
+        self.results = fuzzy_filter(&self.query, keys)
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(key, m)| (key.clone(), m))
+            .collect();
+        self.selected = self.selected.min(self.results.len().saturating_sub(1));
+    }
+
+    /// Handle a key press, the results must be updated if the query changed.
+    pub fn handle_key(&mut self, event: KeyEvent) -> (SearchAction, bool) {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            KeyCode::Esc => return (SearchAction::Close, false),
+            KeyCode::Char('c') if ctrl => return (SearchAction::Close, false),
+            KeyCode::Enter => {
+                return match self.results.get(self.selected) {
+                    Some((key, _)) => (SearchAction::Open(key.clone()), false),
+                    None => (SearchAction::None, false),
+                }
+            }
+            KeyCode::Down => self.selected += 1,
+            KeyCode::Char('n') if ctrl => self.selected += 1,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+                return (SearchAction::None, true);
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.selected = 0;
+                return (SearchAction::None, true);
+            }
+            _ => (),
+        }
+        self.selected = self.selected.min(self.results.len().saturating_sub(1));
+
+        (SearchAction::None, false)
+    }
+
+    /// Print the query, then the results with the matched characters highlighted.
+    pub fn print(&mut self, root: &str, status: &str, width: usize, height: usize) -> Result<()> {
+        let theme = theme::get();
+        execute!(
+            stdout(),
+            Print(theme.title.apply(format!("/{}", escape(&self.query)))),
+            Print(" ".reverse()),
+            Print(theme.dim.apply(format!("  {}", status))),
+            MoveToNextLine(1)
+        )?;
+
+        let visible = height.saturating_sub(2);
+        self.scroll = layout::scroll(self.selected, self.scroll, self.results.len(), visible);
+
+        let root = escape(root);
+        let max_len = width.saturating_sub(render::width(&root) + 3);
+        for (i, (key, m)) in self
+            .results
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(visible)
+        {
+            execute!(
+                stdout(),
+                Print(if i == self.selected { "> " } else { "  " }),
+                Print(theme.dim.apply(root.as_ref()))
+            )?;
+            // the positions of the matches are in chars, each one is escaped on its own
+            let mut used = 0;
+            for (j, c) in key.chars().enumerate() {
+                let c = escape(c.encode_utf8(&mut [0; 4])).into_owned();
+                used += render::width(&c);
+                if used > max_len {
+                    break;
+                }
+                if m.positions.contains(&j) {
+                    execute!(stdout(), Print(theme.matched.apply(c)))?;
+                } else if i == self.selected {
+                    execute!(stdout(), Print(theme.selected.apply(c)))?;
+                } else {
+                    execute!(stdout(), Print(c))?;
+                }
+            }
+            execute!(stdout(), MoveToNextLine(1))?;
+        }
+
+        Ok(())
+    }
+}
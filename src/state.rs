@@ -0,0 +1,188 @@
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, read_to_string, rename, write},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use home::home_dir;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Location of the state file in the home directory, `$VAULTWALKER_STATE` takes precedence.
+const STATE_PATH: &str = ".local/state/vaultwalker/state.json";
+/// Number of recently visited paths kept for each profile.
+const MAX_RECENT: usize = 20;
+
+/// What is remembered between two launches, for each profile.
+#[derive(Serialize, Deserialize, Default)]
+pub struct State {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileState>,
+    /// Where the state is saved, nothing is saved without it
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct ProfileState {
+    /// Full path of each bookmark, directories end with `/`
+    #[serde(default)]
+    pub bookmarks: BTreeMap<String, String>,
+    /// Recently visited directories, the most recent first
+    #[serde(default)]
+    pub recent: Vec<String>,
+    /// Where the browser was left, for each root path
+    #[serde(default)]
+    pub last: BTreeMap<String, Location>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct Location {
+    /// Directory that was open, below the root
+    pub path: String,
+    /// Name of the selected entry
+    pub selected: Option<String>,
+}
+
+impl ProfileState {
+    pub fn visit(&mut self, path: &str) {
+        self.recent.retain(|p| p != path);
+        self.recent.insert(0, path.to_owned());
+        self.recent.truncate(MAX_RECENT);
+    }
+}
+
+impl State {
+    /// Load the state file, a missing file is the same as an empty one.
+    pub fn load() -> Result<Self> {
+        let path = match std::env::var("VAULTWALKER_STATE") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => match home_dir() {
+                Some(home) => home.join(STATE_PATH),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        Self::load_from(path)
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self> {
+        let mut state = match path.exists() {
+            true => serde_json::from_str(&read_to_string(&path)?).map_err(|err| {
+                Error::Application(format!("invalid state file '{}': {}", path.display(), err))
+            })?,
+            false => Self::default(),
+        };
+        state.path = Some(path);
+
+        Ok(state)
+    }
+
+    /// Write the state to a temporary file first, to never leave a truncated file behind.
+    pub fn save(&self) -> Result<()> {
+        let path = match self.path.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        write(&tmp, serde_json::to_string_pretty(self)?)?;
+        rename(tmp, path)?;
+
+        Ok(())
+    }
+
+    pub fn profile(&mut self, key: &str) -> &mut ProfileState {
+        self.profiles.entry(key.to_owned()).or_default()
+    }
+
+    /// Where the browser was left in `root` with the profile `key`, if the location is below it.
+    pub fn last_location(&self, key: &str, root: &str) -> Option<Location> {
+        self.profiles
+            .get(key)
+            .and_then(|p| p.last.get(root))
+            .filter(|l| l.path.starts_with(root))
+            .cloned()
+    }
+
+    /// Path of the bookmark `name` of the profile `key`.
+    pub fn bookmark(&self, key: &str, name: &str) -> Result<String> {
+        self.profiles
+            .get(key)
+            .and_then(|p| p.bookmarks.get(name))
+            .cloned()
+            .ok_or(Error::Application(format!(
+                "unknown bookmark '{}', add it with [B] in the browser",
+                name
+            )))
+    }
+}
+
+/// The state is shared with the Ctrl-C handler, to save it when the program is interrupted.
+#[derive(Clone, Default)]
+pub struct SharedState(Arc<Mutex<State>>);
+
+impl SharedState {
+    pub fn new(state: State) -> Self {
+        Self(Arc::new(Mutex::new(state)))
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, State> {
+        // the state stays usable even if a thread panicked while holding it
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_paths() {
+        let mut state = ProfileState::default();
+        for i in 0..30 {
+            state.visit(&format!("secret/{}/", i));
+        }
+        state.visit("secret/15/");
+
+        assert_eq!(state.recent.len(), MAX_RECENT);
+        assert_eq!(state.recent[0], "secret/15/");
+        assert_eq!(state.recent[1], "secret/29/");
+        assert_eq!(
+            state.recent.iter().filter(|p| *p == "secret/15/").count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_save_state() {
+        let path = std::env::temp_dir().join(format!("vaultwalker-{}.json", std::process::id()));
+        let mut state = State::load_from(path.clone()).unwrap();
+        state
+            .profile("prod")
+            .bookmarks
+            .insert("db".to_owned(), "secret/app/db/".to_owned());
+        state.profile("prod").visit("secret/app/");
+        state.profile("prod").last.insert(
+            "secret/".to_owned(),
+            Location {
+                path: "secret/app/".to_owned(),
+                selected: Some("db/".to_owned()),
+            },
+        );
+        state.save().unwrap();
+
+        let state = State::load_from(path.clone()).unwrap();
+        assert_eq!(state.bookmark("prod", "db").unwrap(), "secret/app/db/");
+        assert!(state.bookmark("staging", "db").is_err());
+        assert_eq!(state.profiles["prod"].recent, vec!["secret/app/"]);
+        let last = state.last_location("prod", "secret/").unwrap();
+        assert_eq!(last.selected.as_deref(), Some("db/"));
+        assert!(state.last_location("prod", "secret/other/").is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
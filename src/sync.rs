@@ -0,0 +1,195 @@
+use std::str::FromStr;
+
+use crate::{
+    client::{HttpClient, VaultClient},
+    diff::TreeDiff,
+    error::{Error, Result},
+};
+
+/// How far the destination is made to look like the source.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SyncMode {
+    /// Only write the keys missing at the destination
+    AddOnly,
+    /// Also overwrite the keys whose value differs
+    Update,
+    /// Also delete the keys that do not exist in the source
+    Mirror,
+}
+
+impl FromStr for SyncMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "add-only" => Ok(SyncMode::AddOnly),
+            "update" => Ok(SyncMode::Update),
+            "mirror" => Ok(SyncMode::Mirror),
+            _ => Err(Error::Application(format!(
+                "unknown sync mode '{}', expected 'add-only', 'update' or 'mirror'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SyncAction {
+    Add,
+    Update,
+    Delete,
+    /// The value differs but the mode does not update existing keys
+    KeepChanged,
+    /// The key only exists at the destination but the mode does not delete
+    KeepExtra,
+}
+
+impl SyncAction {
+    /// Marker and description used when printing the plan.
+    pub fn describe(&self) -> (&'static str, &'static str) {
+        match self {
+            SyncAction::Add => ("+", "missing, will be added"),
+            SyncAction::Update => ("~", "changed, will be updated"),
+            SyncAction::Delete => ("-", "extra, will be deleted"),
+            SyncAction::KeepChanged => ("!", "changed, kept in add-only mode"),
+            SyncAction::KeepExtra => ("?", "extra, kept unless in mirror mode"),
+        }
+    }
+}
+
+pub struct SyncReport {
+    pub written: usize,
+    pub deleted: usize,
+    pub failed: Vec<(String, Error)>,
+}
+
+/// List what a sync in `mode` does for each key that is not already identical.
+pub fn plan(diff: &TreeDiff, mode: SyncMode) -> Vec<(String, SyncAction)> {
+    let mut actions: Vec<(String, SyncAction)> = diff
+        .only_left
+        .iter()
+        .map(|k| (k.clone(), SyncAction::Add))
+        .chain(diff.changed.iter().map(|k| {
+            (
+                k.clone(),
+                match mode {
+                    SyncMode::AddOnly => SyncAction::KeepChanged,
+                    SyncMode::Update | SyncMode::Mirror => SyncAction::Update,
+                },
+            )
+        }))
+        .chain(diff.only_right.iter().map(|k| {
+            (
+                k.clone(),
+                match mode {
+                    SyncMode::Mirror => SyncAction::Delete,
+                    SyncMode::AddOnly | SyncMode::Update => SyncAction::KeepExtra,
+                },
+            )
+        }))
+        .collect();
+    actions.sort_by(|a, b| a.0.cmp(&b.0));
+
+    actions
+}
+
+/// Apply the plan to the destination, a failure does not stop the sync.
+pub fn apply<H: HttpClient>(
+    diff: &TreeDiff,
+    actions: &[(String, SyncAction)],
+    destination: &mut VaultClient<H>,
+    destination_root: &str,
+) -> SyncReport {
+    let mut report = SyncReport {
+        written: 0,
+        deleted: 0,
+        failed: vec![],
+    };
+
+    for (key, action) in actions {
+        let path = format!("{}{}", destination_root, key);
+        let res = match action {
+            SyncAction::Add | SyncAction::Update => destination
+                .write_secret_data(&path, &diff.left_values[key])
+                .map(|_| report.written += 1),
+            SyncAction::Delete => destination
+                .delete_secret(&path)
+                .map(|_| report.deleted += 1),
+            SyncAction::KeepChanged | SyncAction::KeepExtra => Ok(()),
+        };
+
+        if let Err(err) = res {
+            report.failed.push((path, err));
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{FromCache, MockClient},
+        tree::list_keys_recursive,
+    };
+
+    fn setup() -> (VaultClient<MockClient>, VaultClient<MockClient>) {
+        let mut source = VaultClient::new(MockClient::new());
+        let mut destination = VaultClient::new(MockClient::new());
+        source.write_secret("mock/app/new", "a").unwrap();
+        source.write_secret("mock/app/changed", "a").unwrap();
+        destination.write_secret("mock/app/changed", "b").unwrap();
+        destination.write_secret("mock/app/extra", "a").unwrap();
+
+        (source, destination)
+    }
+
+    #[test]
+    fn test_sync_plan() {
+        let (mut source, mut destination) = setup();
+        let diff =
+            TreeDiff::compute(&mut source, "mock/app/", &mut destination, "mock/app/").unwrap();
+
+        assert_eq!(
+            plan(&diff, SyncMode::AddOnly),
+            vec![
+                ("changed".to_owned(), SyncAction::KeepChanged),
+                ("extra".to_owned(), SyncAction::KeepExtra),
+                ("new".to_owned(), SyncAction::Add),
+            ]
+        );
+        assert_eq!(
+            plan(&diff, SyncMode::Mirror),
+            vec![
+                ("changed".to_owned(), SyncAction::Update),
+                ("extra".to_owned(), SyncAction::Delete),
+                ("new".to_owned(), SyncAction::Add),
+            ]
+        );
+        assert!("everything".parse::<SyncMode>().is_err());
+    }
+
+    #[test]
+    fn test_sync_apply() {
+        let (mut source, mut destination) = setup();
+        let diff =
+            TreeDiff::compute(&mut source, "mock/app/", &mut destination, "mock/app/").unwrap();
+
+        let report = apply(
+            &diff,
+            &plan(&diff, SyncMode::Mirror),
+            &mut destination,
+            "mock/app/",
+        );
+        assert_eq!(report.written, 2);
+        assert_eq!(report.deleted, 1);
+        assert!(report.failed.is_empty());
+
+        let keys = list_keys_recursive(&mut destination, "mock/app/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["changed", "new"]);
+        let diff =
+            TreeDiff::compute(&mut source, "mock/app/", &mut destination, "mock/app/").unwrap();
+        assert!(plan(&diff, SyncMode::Mirror).is_empty());
+    }
+}
@@ -0,0 +1,398 @@
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+use serde_derive::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// Built-in themes, `dark` being the default one.
+pub const BUILTIN_THEMES: [&str; 4] = ["dark", "light", "high-contrast", "no-color"];
+
+/// Names of the styles in the `[themes]` tables of the config file.
+pub const STYLES: [&str; 16] = [
+    "title",
+    "path",
+    "selected",
+    "marked",
+    "value",
+    "dim",
+    "matched",
+    "added",
+    "removed",
+    "changed",
+    "note",
+    "info",
+    "error",
+    "status",
+    "profile",
+    "production",
+];
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Use `theme` for the rest of the run, only the first call has an effect.
+pub fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The theme in use, the dark one until another is set.
+pub fn get() -> &'static Theme {
+    THEME.get_or_init(Theme::dark)
+}
+
+/// Colors are disabled by `NO_COLOR`, see https://no-color.org, or on a dumb terminal.
+pub fn colors_disabled() -> bool {
+    std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty())
+        || std::env::var("TERM").is_ok_and(|term| term == "dumb")
+}
+
+/// A color name like `red` or `dark_grey`, or `#rrggbb`.
+pub fn parse_color(name: &str) -> Result<Color> {
+    let rgb = name
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+    match rgb {
+        Some(rgb) => Ok(Color::Rgb {
+            r: (rgb >> 16) as u8,
+            g: (rgb >> 8) as u8,
+            b: rgb as u8,
+        }),
+        None => Color::try_from(name)
+            .map_err(|_| Error::Application(format!("unknown color '{}'", name))),
+    }
+}
+
+/// A style in the config file, e.g. `{ fg = "black", bg = "white", bold = true }`.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StyleConfig {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub dim: bool,
+    #[serde(default)]
+    pub underlined: bool,
+    #[serde(default)]
+    pub reverse: bool,
+}
+
+impl StyleConfig {
+    fn style(&self) -> Result<ContentStyle> {
+        let mut style = ContentStyle::new();
+        style.foreground_color = self.fg.as_deref().map(parse_color).transpose()?;
+        style.background_color = self.bg.as_deref().map(parse_color).transpose()?;
+        for (enabled, attribute) in [
+            (self.bold, Attribute::Bold),
+            (self.dim, Attribute::Dim),
+            (self.underlined, Attribute::Underlined),
+            (self.reverse, Attribute::Reverse),
+        ] {
+            if enabled {
+                style.attributes.set(attribute);
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+/// A theme of the config file, its styles replace the ones of a built-in theme.
+///
+/// ```toml
+/// [themes.solarized]
+/// base = "light"
+/// info = { fg = "#fdf6e3", bg = "#268bd2" }
+/// ```
+#[derive(Deserialize, Default, Clone, Debug)]
+pub struct ThemeConfig {
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub styles: BTreeMap<String, StyleConfig>,
+}
+
+/// Styles of each part of the screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Titles of the views, e.g. the key bindings
+    pub title: ContentStyle,
+    /// Current path above the listing
+    pub path: ContentStyle,
+    /// Selected line of the views
+    pub selected: ContentStyle,
+    /// Entries of the selection of the browser
+    pub marked: ContentStyle,
+    /// Value shown next to the selected key
+    pub value: ContentStyle,
+    /// Separators, previews and other secondary text
+    pub dim: ContentStyle,
+    /// Characters matched by the filter or the search
+    pub matched: ContentStyle,
+    pub added: ContentStyle,
+    pub removed: ContentStyle,
+    pub changed: ContentStyle,
+    /// Reason next to a line of a report, e.g. a key that could not be deleted
+    pub note: ContentStyle,
+    pub info: ContentStyle,
+    pub error: ContentStyle,
+    pub status: ContentStyle,
+    /// Name of the active profile
+    pub profile: ContentStyle,
+    /// Name of the active profile when it is a production one
+    pub production: ContentStyle,
+    /// The accent color of the profiles applies
+    pub colors: bool,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        let style = ContentStyle::new();
+        Self {
+            title: style.bold(),
+            path: style.bold(),
+            selected: style.bold(),
+            marked: style.magenta().bold(),
+            value: style.bold(),
+            dim: style.dim(),
+            matched: style.yellow().bold(),
+            added: style.green(),
+            removed: style.red(),
+            changed: style.yellow(),
+            note: style.red(),
+            info: style.black().on_white(),
+            error: style.white().on_red(),
+            status: style.black().on_grey(),
+            profile: style.black().on_cyan(),
+            production: style.white().on_red().bold(),
+            colors: true,
+        }
+    }
+
+    /// Darker colors, readable on a light background.
+    pub fn light() -> Self {
+        let style = ContentStyle::new();
+        Self {
+            marked: style.dark_magenta().bold(),
+            matched: style.dark_yellow().bold(),
+            added: style.dark_green(),
+            removed: style.dark_red(),
+            changed: style.dark_yellow(),
+            note: style.dark_red(),
+            info: style.white().on_black(),
+            error: style.white().on_dark_red(),
+            status: style.white().on_dark_grey(),
+            profile: style.white().on_dark_cyan(),
+            production: style.white().on_dark_red().bold(),
+            ..Self::dark()
+        }
+    }
+
+    /// Bright colors and no dimmed text.
+    pub fn high_contrast() -> Self {
+        let style = ContentStyle::new();
+        Self {
+            title: style.white().bold().underlined(),
+            selected: style.black().on_white().bold(),
+            marked: style.black().on_magenta().bold(),
+            dim: style,
+            matched: style.black().on_yellow().bold(),
+            added: style.green().bold(),
+            removed: style.red().bold(),
+            changed: style.yellow().bold(),
+            note: style.red().bold(),
+            info: style.black().on_white().bold(),
+            error: style.white().on_red().bold(),
+            status: style.black().on_white(),
+            profile: style.black().on_cyan().bold(),
+            ..Self::dark()
+        }
+    }
+
+    /// Only bold, underlined and reversed text, for `NO_COLOR` and the dumb terminals.
+    pub fn no_color() -> Self {
+        let style = ContentStyle::new();
+        Self {
+            title: style.bold(),
+            path: style.bold(),
+            selected: style.bold(),
+            marked: style.reverse(),
+            value: style.bold(),
+            dim: style,
+            matched: style.underlined(),
+            added: style.bold(),
+            removed: style.bold(),
+            changed: style.bold(),
+            note: style,
+            info: style.reverse(),
+            error: style.reverse().bold(),
+            status: style.reverse(),
+            profile: style.reverse(),
+            production: style.reverse().bold(),
+            colors: false,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "no-color" => Some(Self::no_color()),
+            _ => None,
+        }
+    }
+
+    /// The theme called `name`, from `themes` or a built-in one.
+    pub fn named(name: &str, themes: &BTreeMap<String, ThemeConfig>) -> Result<Self> {
+        let config = match themes.get(name) {
+            Some(config) => config,
+            None => {
+                return Self::builtin(name).ok_or_else(|| {
+                    let mut names: Vec<&str> = BUILTIN_THEMES.to_vec();
+                    names.extend(themes.keys().map(String::as_str));
+                    Error::Application(format!(
+                        "unknown theme '{}', the themes are: {}",
+                        name,
+                        names.join(", ")
+                    ))
+                })
+            }
+        };
+
+        // a user theme is based on a built-in one, not on another user theme
+        let base = config.base.as_deref().unwrap_or("dark");
+        let mut theme = Self::builtin(base).ok_or_else(|| {
+            Error::Application(format!(
+                "[themes.{}]: unknown base theme '{}', the built-in themes are: {}",
+                name,
+                base,
+                BUILTIN_THEMES.join(", ")
+            ))
+        })?;
+        for (style_name, style) in config.styles.iter() {
+            let style = style
+                .style()
+                .map_err(|err| Error::Application(format!("[themes.{}]: {}", name, err)))?;
+            match theme.style_mut(style_name) {
+                Some(slot) => *slot = style,
+                None => {
+                    return Err(Error::Application(format!(
+                        "[themes.{}]: unknown style '{}', the styles are: {}",
+                        name,
+                        style_name,
+                        STYLES.join(", ")
+                    )))
+                }
+            }
+        }
+
+        Ok(theme)
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut ContentStyle> {
+        match name {
+            "title" => Some(&mut self.title),
+            "path" => Some(&mut self.path),
+            "selected" => Some(&mut self.selected),
+            "marked" => Some(&mut self.marked),
+            "value" => Some(&mut self.value),
+            "dim" => Some(&mut self.dim),
+            "matched" => Some(&mut self.matched),
+            "added" => Some(&mut self.added),
+            "removed" => Some(&mut self.removed),
+            "changed" => Some(&mut self.changed),
+            "note" => Some(&mut self.note),
+            "info" => Some(&mut self.info),
+            "error" => Some(&mut self.error),
+            "status" => Some(&mut self.status),
+            "profile" => Some(&mut self.profile),
+            "production" => Some(&mut self.production),
+            _ => None,
+        }
+    }
+
+    /// Style of the label of a profile, on its accent color when it has one.
+    pub fn profile_label(&self, production: bool, accent: Option<Color>) -> ContentStyle {
+        let mut style = match production {
+            true => self.production,
+            false => self.profile,
+        };
+        if let Some(accent) = accent.filter(|_| self.colors) {
+            style.background_color = Some(accent);
+        }
+
+        style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn themes(content: &str) -> BTreeMap<String, ThemeConfig> {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("dark_grey").unwrap(), Color::DarkGrey);
+        assert_eq!(
+            parse_color("#268bd2").unwrap(),
+            Color::Rgb {
+                r: 0x26,
+                g: 0x8b,
+                b: 0xd2
+            }
+        );
+        assert_eq!(
+            parse_color("pink").unwrap_err().to_string(),
+            "unknown color 'pink'"
+        );
+        assert!(parse_color("#12345").is_err());
+    }
+
+    #[test]
+    fn test_theme() {
+        for name in BUILTIN_THEMES {
+            assert!(Theme::named(name, &BTreeMap::new()).is_ok());
+        }
+        assert_eq!(
+            Theme::named("solarized", &BTreeMap::new())
+                .unwrap_err()
+                .to_string(),
+            "unknown theme 'solarized', the themes are: dark, light, high-contrast, no-color"
+        );
+
+        let themes =
+            themes("[mine]\nbase = 'light'\ninfo = { fg = 'black', bg = 'white', bold = true }");
+        let theme = Theme::named("mine", &themes).unwrap();
+        assert_eq!(theme.info, ContentStyle::new().black().on_white().bold());
+        assert_eq!(theme.error, Theme::light().error);
+
+        let themes = self::themes("[mine]\ninfo = { fg = 'pink' }");
+        assert_eq!(
+            Theme::named("mine", &themes).unwrap_err().to_string(),
+            "[themes.mine]: unknown color 'pink'"
+        );
+        let themes = self::themes("[mine]\nbackground = { fg = 'red' }");
+        assert!(Theme::named("mine", &themes)
+            .unwrap_err()
+            .to_string()
+            .starts_with("[themes.mine]: unknown style 'background'"));
+
+        // the accent color is ignored without colors
+        let theme = Theme::dark();
+        assert_eq!(
+            theme
+                .profile_label(true, Some(Color::Blue))
+                .background_color,
+            Some(Color::Blue)
+        );
+        assert_eq!(
+            Theme::no_color().profile_label(false, Some(Color::Blue)),
+            Theme::no_color().profile
+        );
+    }
+}
@@ -0,0 +1,780 @@
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use crate::{
+    client::{DeleteMode, FromCache, HttpClient, KvVersion, VaultClient, VaultSecret},
+    error::{Error, Result},
+};
+
+/// Same limit as the navigation, to avoid looping forever on a misbehaving server.
+const MAX_DEPTH: usize = 32;
+
+/// Walk of a subtree, listing one directory per step.
+///
+/// Each step is one request, `TreeIndex` runs the steps on a worker thread.
+pub struct TreeWalker {
+    root: String,
+    /// Directories left to list, relative to the root
+    stack: Vec<String>,
+    /// Keys found so far, relative to the root, in the listed order
+    pub keys: Vec<String>,
+}
+
+impl TreeWalker {
+    /// Start a walk of the directory `root`, which must end with a `/`.
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: root.to_string(),
+            stack: vec![String::new()],
+            keys: vec![],
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// List the next directory, an empty or missing directory is skipped.
+    ///
+    /// On error the directory is skipped as well, so the walk can go on.
+    pub fn step<H: HttpClient>(
+        &mut self,
+        client: &mut VaultClient<H>,
+        cache: FromCache,
+    ) -> Result<()> {
+        let dir = match self.stack.pop() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        if dir.matches('/').count() > MAX_DEPTH {
+            return Err(Error::Application(format!(
+                "the directory '{}{}' is too deep",
+                self.root, dir
+            )));
+        }
+
+        let res = match client.list_secrets(&format!("{}{}", self.root, dir), cache) {
+            Ok(res) => res,
+            Err(Error::NotFound(_)) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        // push the sub directories in reverse to visit them in the listed order
+        for key in res.keys.iter().rev() {
+            if key.ends_with('/') {
+                self.stack.push(format!("{}{}", dir, key));
+            }
+        }
+        self.keys.extend(
+            res.keys
+                .iter()
+                .filter(|k| !k.ends_with('/'))
+                .map(|k| format!("{}{}", dir, k)),
+        );
+
+        Ok(())
+    }
+}
+
+/// Keys below a directory, listed by a worker thread while the browser handles the input.
+pub struct TreeIndex {
+    root: String,
+    /// Keys received so far, relative to the root, in the listed order
+    pub keys: Vec<String>,
+    /// Number of directories that could not be listed
+    pub errors: usize,
+    /// Keys found in each listed directory, `None` once the walk is over
+    receiver: Option<Receiver<Result<Vec<String>>>>,
+}
+
+impl TreeIndex {
+    /// Start walking the directory `root` with a copy of `client`.
+    ///
+    /// The walk stops once the index is dropped.
+    pub fn new<H: HttpClient + Send + 'static>(mut client: VaultClient<H>, root: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let mut walker = TreeWalker::new(root);
+        thread::spawn(move || {
+            while !walker.is_done() {
+                let res = walker
+                    .step(&mut client, FromCache::Yes)
+                    .map(|_| std::mem::take(&mut walker.keys));
+                if sender.send(res).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            root: root.to_string(),
+            keys: vec![],
+            errors: 0,
+            receiver: Some(receiver),
+        }
+    }
+
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.receiver.is_none()
+    }
+
+    /// Take what the worker found since the last call, without waiting.
+    ///
+    /// Returns whether the keys or the end of the walk changed anything.
+    pub fn receive(&mut self) -> bool {
+        let receiver = match self.receiver.as_ref() {
+            Some(receiver) => receiver,
+            None => return false,
+        };
+
+        let mut changed = false;
+        loop {
+            match receiver.try_recv() {
+                Ok(Ok(keys)) => {
+                    changed |= !keys.is_empty();
+                    self.keys.extend(keys);
+                }
+                Ok(Err(_)) => self.errors += 1,
+                Err(TryRecvError::Empty) => return changed,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+/// List every key below the directory `root`, sorted.
+///
+/// The returned paths are relative to `root`, an empty or missing directory gives an empty list.
+pub fn list_keys_recursive<H: HttpClient>(
+    client: &mut VaultClient<H>,
+    root: &str,
+    cache: FromCache,
+) -> Result<Vec<String>> {
+    let mut walker = TreeWalker::new(root);
+    while !walker.is_done() {
+        walker.step(client, cache)?;
+    }
+
+    let mut keys = walker.keys;
+    keys.sort();
+
+    Ok(keys)
+}
+
+/// Check if a single key exists, by listing its parent directory.
+fn key_exists<H: HttpClient>(client: &mut VaultClient<H>, path: &str) -> Result<bool> {
+    let (parent, name) = match path.rfind('/') {
+        Some(i) => (&path[..=i], &path[i + 1..]),
+        None => return Ok(false),
+    };
+
+    match client.list_secrets(parent, FromCache::No) {
+        Ok(res) => Ok(res.keys.iter().any(|k| k == name)),
+        Err(Error::NotFound(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Full path of every key of `entries`, the keys and directories of `directory`, the
+/// directories being listed recursively.
+pub fn expand_entries<H: HttpClient>(
+    client: &mut VaultClient<H>,
+    directory: &str,
+    entries: &[String],
+) -> Result<Vec<String>> {
+    let mut keys = vec![];
+    for entry in entries {
+        let path = format!("{}{}", directory, entry);
+        match entry.ends_with('/') {
+            true => keys.extend(
+                list_keys_recursive(client, &path, FromCache::No)?
+                    .iter()
+                    .map(|key| format!("{}{}", path, key)),
+            ),
+            false => keys.push(path),
+        }
+    }
+
+    Ok(keys)
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum TransferKind {
+    Copy,
+    Move,
+}
+
+impl TransferKind {
+    pub fn verb(&self) -> &'static str {
+        match self {
+            TransferKind::Copy => "copy",
+            TransferKind::Move => "move",
+        }
+    }
+}
+
+/// What to do with the keys that already exist at the destination.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ConflictPolicy {
+    Abort,
+    Skip,
+    Overwrite,
+}
+
+pub struct TransferItem {
+    pub source: String,
+    pub destination: String,
+    /// True if the destination already exists
+    pub conflict: bool,
+}
+
+/// List of the keys affected by a copy or a move, computed before touching anything.
+pub struct TransferPlan {
+    pub kind: TransferKind,
+    pub source: String,
+    pub destination: String,
+    pub items: Vec<TransferItem>,
+    /// Number of entries of `source` transferred together, `None` for a single key or directory
+    pub entries: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TransferReport {
+    pub transferred: usize,
+    pub skipped: usize,
+}
+
+impl TransferPlan {
+    /// Plan the transfer of `source` to `destination`.
+    ///
+    /// A source ending with `/` is a directory and is transferred recursively, the destination is
+    /// then the new directory. When a single key is transferred to a destination ending with `/`,
+    /// the key keeps its name.
+    pub fn new<H: HttpClient>(
+        client: &mut VaultClient<H>,
+        kind: TransferKind,
+        source: &str,
+        destination: &str,
+    ) -> Result<Self> {
+        let mut destination = destination.trim_start_matches('/').to_string();
+        let mut items = vec![];
+
+        if source.ends_with('/') {
+            if !destination.ends_with('/') {
+                destination.push('/');
+            }
+            if destination.starts_with(source) {
+                return Err(Error::Application(format!(
+                    "cannot {} the directory '{}' into itself",
+                    kind.verb(),
+                    source
+                )));
+            }
+
+            let keys = list_keys_recursive(client, source, FromCache::No)?;
+            if keys.is_empty() {
+                return Err(Error::Application(format!(
+                    "the directory '{}' is empty",
+                    source
+                )));
+            }
+            let existing = list_keys_recursive(client, &destination, FromCache::No)?;
+            for key in keys {
+                items.push(TransferItem {
+                    source: format!("{}{}", source, key),
+                    destination: format!("{}{}", destination, key),
+                    conflict: existing.contains(&key),
+                });
+            }
+        } else {
+            if destination.ends_with('/') {
+                destination.push_str(source.rsplit('/').next().unwrap_or(source));
+            }
+            if destination == source {
+                return Err(Error::Application(format!(
+                    "cannot {} the key '{}' onto itself",
+                    kind.verb(),
+                    source
+                )));
+            }
+
+            items.push(TransferItem {
+                source: source.to_string(),
+                conflict: key_exists(client, &destination)?,
+                destination: destination.clone(),
+            });
+        }
+
+        Ok(Self {
+            kind,
+            source: source.to_string(),
+            destination,
+            items,
+            entries: None,
+        })
+    }
+
+    /// Plan the transfer of some keys and directories of `directory` into the `destination`
+    /// directory, where they keep their names.
+    pub fn for_entries<H: HttpClient>(
+        client: &mut VaultClient<H>,
+        kind: TransferKind,
+        directory: &str,
+        entries: &[String],
+        destination: &str,
+    ) -> Result<Self> {
+        let mut destination = destination.trim_start_matches('/').to_string();
+        if !destination.ends_with('/') {
+            destination.push('/');
+        }
+
+        let mut items = vec![];
+        for entry in entries {
+            let plan = Self::new(
+                client,
+                kind,
+                &format!("{}{}", directory, entry),
+                &format!("{}{}", destination, entry),
+            )?;
+            items.extend(plan.items);
+        }
+
+        Ok(Self {
+            kind,
+            source: directory.to_string(),
+            destination,
+            items,
+            entries: Some(entries.len()),
+        })
+    }
+
+    pub fn conflicts(&self) -> usize {
+        self.items.iter().filter(|i| i.conflict).count()
+    }
+
+    /// Run the transfer.
+    ///
+    /// All the sources are read before writing anything. If a write fails, the destinations
+    /// already written are restored to their previous state, and for a move the sources are only
+    /// deleted once every destination has been written.
+    pub fn execute<H: HttpClient>(
+        &self,
+        client: &mut VaultClient<H>,
+        policy: ConflictPolicy,
+    ) -> Result<TransferReport> {
+        if policy == ConflictPolicy::Abort && self.conflicts() > 0 {
+            return Err(Error::Application(format!(
+                "{} keys already exist at the destination",
+                self.conflicts()
+            )));
+        }
+
+        let items: Vec<&TransferItem> = self
+            .items
+            .iter()
+            .filter(|i| !i.conflict || policy == ConflictPolicy::Overwrite)
+            .collect();
+
+        // read everything before writing, including the previous value of the overwritten keys
+        let mut secrets = Vec::with_capacity(items.len());
+        for item in &items {
+            let backup = match item.conflict {
+                true => Some(client.get_secret::<VaultSecret>(&item.destination, FromCache::No)?),
+                false => None,
+            };
+            secrets.push((
+                client.get_secret::<VaultSecret>(&item.source, FromCache::No)?,
+                backup,
+            ));
+        }
+
+        // previous value of each written destination, `None` if it did not exist
+        let mut written: Vec<(&str, Option<VaultSecret>)> = vec![];
+        for (item, (secret, backup)) in items.iter().zip(secrets) {
+            if let Err(err) = client.write_secret_data(&item.destination, &secret) {
+                let failed_rollbacks = Self::rollback(client, &written);
+                return Err(Error::Application(format!(
+                    "failed to write '{}' ({}), {} keys rolled back{}",
+                    item.destination,
+                    err,
+                    written.len() - failed_rollbacks.len(),
+                    match failed_rollbacks.is_empty() {
+                        true => String::new(),
+                        false => format!(", could not restore {}", failed_rollbacks.join(", ")),
+                    }
+                )));
+            }
+            written.push((&item.destination, backup));
+        }
+
+        if self.kind == TransferKind::Move {
+            let mut failed = vec![];
+            for item in &items {
+                if client.delete_secret(&item.source).is_err() {
+                    failed.push(item.source.as_str());
+                }
+            }
+
+            if !failed.is_empty() {
+                return Err(Error::Application(format!(
+                    "all keys were copied but the sources could not be deleted: {}",
+                    failed.join(", ")
+                )));
+            }
+        }
+
+        Ok(TransferReport {
+            transferred: items.len(),
+            skipped: self.items.len() - items.len(),
+        })
+    }
+
+    /// Restore the written destinations, returns the ones that could not be restored.
+    fn rollback<'a, H: HttpClient>(
+        client: &mut VaultClient<H>,
+        written: &[(&'a str, Option<VaultSecret>)],
+    ) -> Vec<&'a str> {
+        let mut failed = vec![];
+        for (path, backup) in written.iter().rev() {
+            let res = match backup {
+                Some(secret) => client.write_secret_data(path, secret),
+                None => client.delete_secret(path),
+            };
+            if res.is_err() {
+                failed.push(*path);
+            }
+        }
+
+        failed
+    }
+}
+
+/// List of the keys below a directory about to be deleted.
+pub struct DeletePlan {
+    pub directory: String,
+    /// Full path of every key
+    pub keys: Vec<String>,
+    pub version: KvVersion,
+    /// Number of entries of `directory` deleted together, `None` when the whole directory is
+    pub entries: Option<usize>,
+}
+
+pub struct DeleteReport {
+    pub deleted: usize,
+    pub failed: Vec<(String, Error)>,
+}
+
+impl DeletePlan {
+    pub fn new<H: HttpClient>(client: &mut VaultClient<H>, directory: &str) -> Result<Self> {
+        let keys = list_keys_recursive(client, directory, FromCache::No)?;
+        if keys.is_empty() {
+            return Err(Error::Application(format!(
+                "the directory '{}' is empty",
+                directory
+            )));
+        }
+
+        Ok(Self {
+            directory: directory.to_string(),
+            keys: keys.iter().map(|k| format!("{}{}", directory, k)).collect(),
+            version: client.mount(directory).version,
+            entries: None,
+        })
+    }
+
+    /// Plan the deletion of some keys and directories of `directory`.
+    pub fn for_entries<H: HttpClient>(
+        client: &mut VaultClient<H>,
+        directory: &str,
+        entries: &[String],
+    ) -> Result<Self> {
+        let keys = expand_entries(client, directory, entries)?;
+        if keys.is_empty() {
+            return Err(Error::Application("nothing to delete".to_owned()));
+        }
+
+        Ok(Self {
+            directory: directory.to_string(),
+            keys,
+            version: client.mount(directory).version,
+            entries: Some(entries.len()),
+        })
+    }
+
+    /// Delete every key, calling `progress` with the number of keys processed so far.
+    ///
+    /// A failure does not stop the deletion, the failed keys are listed in the report.
+    pub fn execute<H: HttpClient>(
+        &self,
+        client: &mut VaultClient<H>,
+        mode: DeleteMode,
+        mut progress: impl FnMut(usize),
+    ) -> DeleteReport {
+        let mut report = DeleteReport {
+            deleted: 0,
+            failed: vec![],
+        };
+        for (i, key) in self.keys.iter().enumerate() {
+            match client.delete_secret_with(key, mode) {
+                Ok(()) => report.deleted += 1,
+                Err(err) => report.failed.push((key.clone(), err)),
+            }
+            progress(i + 1);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::MockClient;
+
+    #[test]
+    fn test_list_keys_recursive() {
+        let mut client = VaultClient::new(MockClient::new());
+
+        let keys = list_keys_recursive(&mut client, "mock/key9/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["nested", "other"]);
+
+        let keys = list_keys_recursive(&mut client, "mock/key15/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["deep/key"]);
+
+        let keys = list_keys_recursive(&mut client, "mock/missing/", FromCache::No).unwrap();
+        assert!(keys.is_empty());
+    }
+
+    #[test]
+    fn test_tree_index() {
+        let mut index = TreeIndex::new(VaultClient::new(MockClient::new()), "mock/key9/");
+        while !index.is_done() {
+            index.receive();
+            thread::yield_now();
+        }
+        assert_eq!(index.keys, vec!["nested", "other"]);
+        assert_eq!(index.errors, 0);
+        assert!(!index.receive());
+    }
+
+    #[test]
+    fn test_copy_directory_across_kv_versions() {
+        let mut client = VaultClient::new(MockClient::new());
+
+        let plan = TransferPlan::new(&mut client, TransferKind::Copy, "mock/key9/", "mockv2/key9")
+            .unwrap();
+        assert_eq!(plan.destination, "mockv2/key9/");
+        assert_eq!(plan.items.len(), 2);
+        assert_eq!(plan.conflicts(), 0);
+        assert_eq!(
+            plan.execute(&mut client, ConflictPolicy::Abort).unwrap(),
+            TransferReport {
+                transferred: 2,
+                skipped: 0
+            }
+        );
+
+        let keys = list_keys_recursive(&mut client, "mockv2/key9/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["nested", "other"]);
+        let keys = list_keys_recursive(&mut client, "mock/key9/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["nested", "other"]);
+    }
+
+    #[test]
+    fn test_move_with_conflicts() {
+        let mut client = VaultClient::new(MockClient::new());
+        client.write_secret("mockv2/app/nested", "old").unwrap();
+
+        let plan = TransferPlan::new(&mut client, TransferKind::Move, "mock/key9/", "mockv2/app/")
+            .unwrap();
+        assert_eq!(plan.conflicts(), 1);
+        assert!(plan.execute(&mut client, ConflictPolicy::Abort).is_err());
+        assert_eq!(
+            plan.execute(&mut client, ConflictPolicy::Skip).unwrap(),
+            TransferReport {
+                transferred: 1,
+                skipped: 1
+            }
+        );
+
+        // the skipped key is left untouched on both sides
+        let keys = list_keys_recursive(&mut client, "mock/key9/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["nested"]);
+        let secret: VaultSecret = client
+            .get_secret("mockv2/app/nested", FromCache::No)
+            .unwrap();
+        assert_eq!(String::from(&secret), "old");
+    }
+
+    #[test]
+    fn test_rollback_on_failed_write() {
+        let mut client = VaultClient::new(MockClient::new().deny_writes("mock/dst/b"));
+        client.write_secret("mock/src/a", "a").unwrap();
+        client.write_secret("mock/src/b", "b").unwrap();
+
+        let plan =
+            TransferPlan::new(&mut client, TransferKind::Move, "mock/src/", "mock/dst/").unwrap();
+        assert_eq!(
+            plan.execute(&mut client, ConflictPolicy::Abort)
+                .unwrap_err()
+                .to_string(),
+            "failed to write 'mock/dst/b' (vault error: permission denied), 1 keys rolled back"
+        );
+
+        // nothing was written and the sources are still there
+        let keys = list_keys_recursive(&mut client, "mock/dst/", FromCache::No).unwrap();
+        assert!(keys.is_empty());
+        let keys = list_keys_recursive(&mut client, "mock/src/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_invalid_transfers() {
+        let mut client = VaultClient::new(MockClient::new());
+
+        assert_eq!(
+            TransferPlan::new(
+                &mut client,
+                TransferKind::Copy,
+                "mock/key9/",
+                "mock/key9/sub/"
+            )
+            .err()
+            .unwrap()
+            .to_string(),
+            "cannot copy the directory 'mock/key9/' into itself"
+        );
+        assert_eq!(
+            TransferPlan::new(&mut client, TransferKind::Move, "mock/key2", "mock/")
+                .err()
+                .unwrap()
+                .to_string(),
+            "cannot move the key 'mock/key2' onto itself"
+        );
+
+        let plan =
+            TransferPlan::new(&mut client, TransferKind::Move, "mock/key2", "mock/key3").unwrap();
+        assert_eq!(plan.conflicts(), 1);
+    }
+
+    #[test]
+    fn test_delete_directory() {
+        let mut client = VaultClient::new(MockClient::new().deny_writes("mock/key9/other"));
+
+        let plan = DeletePlan::new(&mut client, "mock/key9/").unwrap();
+        assert_eq!(plan.keys, vec!["mock/key9/nested", "mock/key9/other"]);
+        assert_eq!(plan.version, KvVersion::V1);
+
+        let mut progress = vec![];
+        let report = plan.execute(&mut client, DeleteMode::Metadata, |i| progress.push(i));
+        assert_eq!(progress, vec![1, 2]);
+        assert_eq!(report.deleted, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "mock/key9/other");
+    }
+
+    #[test]
+    fn test_delete_entries() {
+        let mut client = VaultClient::new(MockClient::new());
+
+        let entries = vec!["key2".to_owned(), "key9/".to_owned()];
+        let plan = DeletePlan::for_entries(&mut client, "mock/", &entries).unwrap();
+        assert_eq!(plan.entries, Some(2));
+        assert_eq!(
+            plan.keys,
+            vec!["mock/key2", "mock/key9/nested", "mock/key9/other"]
+        );
+        let report = plan.execute(&mut client, DeleteMode::Metadata, |_| ());
+        assert_eq!(report.deleted, 3);
+        let keys = client.list_secrets("mock/", FromCache::No).unwrap().keys;
+        assert!(!keys.contains(&"key2".to_owned()) && !keys.contains(&"key9/".to_owned()));
+
+        assert!(DeletePlan::for_entries(&mut client, "mock/", &[]).is_err());
+    }
+
+    #[test]
+    fn test_transfer_entries() {
+        let mut client = VaultClient::new(MockClient::new());
+
+        let entries = vec!["key2".to_owned(), "key9/".to_owned()];
+        let plan = TransferPlan::for_entries(
+            &mut client,
+            TransferKind::Move,
+            "mock/",
+            &entries,
+            "mock/key15",
+        )
+        .unwrap();
+        assert_eq!(plan.destination, "mock/key15/");
+        let destinations: Vec<&str> = plan.items.iter().map(|i| i.destination.as_str()).collect();
+        assert_eq!(
+            destinations,
+            vec![
+                "mock/key15/key2",
+                "mock/key15/key9/nested",
+                "mock/key15/key9/other"
+            ]
+        );
+        plan.execute(&mut client, ConflictPolicy::Abort).unwrap();
+        let keys = list_keys_recursive(&mut client, "mock/key15/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["deep/key", "key2", "key9/nested", "key9/other"]);
+
+        // a directory cannot go into itself
+        let entries = vec!["key15/".to_owned()];
+        assert!(TransferPlan::for_entries(
+            &mut client,
+            TransferKind::Move,
+            "mock/",
+            &entries,
+            "mock/key15/"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_soft_delete_directory() {
+        let mut client = VaultClient::new(MockClient::new());
+
+        let plan = DeletePlan::new(&mut client, "mockv2/app/").unwrap();
+        assert_eq!(plan.version, KvVersion::V2);
+        let report = plan.execute(&mut client, DeleteMode::Soft, |_| ());
+        assert_eq!(report.deleted, 1);
+
+        // soft deleted keys are still listed but cannot be read anymore
+        let keys = list_keys_recursive(&mut client, "mockv2/app/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["key"]);
+        assert!(client
+            .get_secret::<VaultSecret>("mockv2/app/key", FromCache::No)
+            .is_err());
+
+        let report = plan.execute(&mut client, DeleteMode::Metadata, |_| ());
+        assert_eq!(report.deleted, 1);
+        let keys = list_keys_recursive(&mut client, "mockv2/app/", FromCache::No).unwrap();
+        assert!(keys.is_empty());
+    }
+
+    #[test]
+    fn test_move_soft_deletes_the_source() {
+        let mut client = VaultClient::new(MockClient::new());
+
+        let plan =
+            TransferPlan::new(&mut client, TransferKind::Move, "mockv2/app/", "mock/app/").unwrap();
+        let report = plan.execute(&mut client, ConflictPolicy::Skip).unwrap();
+        assert_eq!(report.transferred, 1);
+
+        // the versions of the source are kept, it can still be undeleted
+        let keys = list_keys_recursive(&mut client, "mockv2/app/", FromCache::No).unwrap();
+        assert_eq!(keys, vec!["key"]);
+        assert!(client
+            .get_secret::<VaultSecret>("mockv2/app/key", FromCache::No)
+            .is_err());
+    }
+}
@@ -0,0 +1,397 @@
+use std::io::Write;
+
+use zeroize::Zeroizing;
+
+use crate::{
+    client::{FromCache, HttpClient, VaultClient, VaultSecret},
+    error::{Error, Result},
+    tree::{ConflictPolicy, DeletePlan, TransferKind, TransferPlan},
+};
+
+/// Changes kept at most, the oldest ones are forgotten first.
+const UNDO_LIMIT: usize = 100;
+
+/// Appended to the message of a change that could not be recorded.
+const NOT_UNDOABLE: &str = ", not undoable as the previous content could not be read";
+
+/// A secret as JSON, wiped from memory when dropped, `None` if the key does not exist.
+pub type Snapshot = Option<Zeroizing<String>>;
+
+/// Content of `path`, the secret read is wiped once serialized.
+pub fn read_state<H: HttpClient>(client: &mut VaultClient<H>, path: &str) -> Result<Snapshot> {
+    match client.get_secret::<VaultSecret>(path, FromCache::No) {
+        Ok(secret) => snapshot(Some(&Zeroizing::new(secret))),
+        Err(Error::NotFound(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Counts the bytes written, to size a buffer before writing to it.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialize `secret` in a buffer of the right size, growing it would leave copies behind.
+pub fn snapshot(secret: Option<&VaultSecret>) -> Result<Snapshot> {
+    let secret = match secret {
+        Some(secret) => secret,
+        None => return Ok(None),
+    };
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, secret)?;
+    let mut json = Zeroizing::new(Vec::with_capacity(counter.0));
+    serde_json::to_writer(&mut *json, secret)?;
+
+    match String::from_utf8(std::mem::take(&mut *json)) {
+        Ok(json) => Ok(Some(Zeroizing::new(json))),
+        Err(err) => {
+            drop(Zeroizing::new(err.into_bytes()));
+            Err(Error::Application("a secret is not valid UTF-8".to_owned()))
+        }
+    }
+}
+
+fn restore(json: &str) -> Result<Zeroizing<VaultSecret>> {
+    Ok(Zeroizing::new(serde_json::from_str(json)?))
+}
+
+struct KeyChange {
+    path: String,
+    before: Snapshot,
+    /// What the change wrote, to notice if the key was changed since
+    after: Snapshot,
+}
+
+/// A change of some keys, with their content before and after it.
+pub struct Change {
+    /// e.g. `update of 'secret/key'`
+    pub description: String,
+    keys: Vec<KeyChange>,
+}
+
+impl Change {
+    /// A change of some keys, each with its content before and after it.
+    pub fn new(description: String, keys: Vec<(String, Snapshot, Snapshot)>) -> Self {
+        Self {
+            description,
+            keys: keys
+                .into_iter()
+                .map(|(path, before, after)| KeyChange {
+                    path,
+                    before,
+                    after,
+                })
+                .collect(),
+        }
+    }
+
+    /// Read the keys `plan` is about to write with `policy`, and its sources for a move.
+    pub fn for_transfer<H: HttpClient>(
+        client: &mut VaultClient<H>,
+        description: String,
+        plan: &TransferPlan,
+        policy: ConflictPolicy,
+    ) -> Result<Self> {
+        let mut keys = vec![];
+        for item in plan.items.iter() {
+            if item.conflict && policy != ConflictPolicy::Overwrite {
+                continue;
+            }
+            let source = read_state(client, &item.source)?;
+            let destination = read_state(client, &item.destination)?;
+            keys.push((item.destination.clone(), destination, source.clone()));
+            if plan.kind == TransferKind::Move {
+                keys.push((item.source.clone(), source, None));
+            }
+        }
+
+        Ok(Self::new(description, keys))
+    }
+
+    /// Read the keys `plan` is about to delete, calling `progress` with the number read so far.
+    pub fn for_delete<H: HttpClient>(
+        client: &mut VaultClient<H>,
+        description: String,
+        plan: &DeletePlan,
+        mut progress: impl FnMut(usize),
+    ) -> Result<Self> {
+        let mut keys = vec![];
+        for (i, key) in plan.keys.iter().enumerate() {
+            keys.push((key.clone(), read_state(client, key)?, None));
+            progress(i + 1);
+        }
+
+        Ok(Self::new(description, keys))
+    }
+
+    /// Forget the keys the change did not modify in the end, e.g. the ones that failed.
+    pub fn without(mut self, paths: &[&str]) -> Self {
+        self.keys.retain(|key| !paths.contains(&key.path.as_str()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The keys with `true` when undoing deletes them, they did not exist before the change.
+    pub fn keys(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.keys
+            .iter()
+            .map(|key| (key.path.as_str(), key.before.is_none()))
+    }
+
+    /// The keys whose content is not the one written by the change anymore.
+    pub fn modified_since<H: HttpClient>(
+        &self,
+        client: &mut VaultClient<H>,
+    ) -> Result<Vec<String>> {
+        let mut modified = vec![];
+        for key in self.keys.iter() {
+            if read_state(client, &key.path)? != key.after {
+                modified.push(key.path.clone());
+            }
+        }
+
+        Ok(modified)
+    }
+
+    /// Put every key back as it was before the change, in the reverse order.
+    ///
+    /// The keys restored are removed from the change, only the failed ones are left.
+    pub fn revert<H: HttpClient>(&mut self, client: &mut VaultClient<H>) -> Result<()> {
+        let mut failed = vec![];
+        let mut kept = vec![];
+        while let Some(key) = self.keys.pop() {
+            let res = match key.before.as_ref() {
+                Some(json) => {
+                    restore(json).and_then(|secret| client.write_secret_data(&key.path, &secret))
+                }
+                None => client.delete_secret(&key.path),
+            };
+            if let Err(err) = res {
+                failed.push(format!("{} ({})", key.path, err));
+                kept.push(key);
+            }
+        }
+        kept.reverse();
+        self.keys = kept;
+
+        match failed.is_empty() {
+            true => Ok(()),
+            false => Err(Error::Application(format!(
+                "could not restore {}",
+                failed.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Changes made in the browser, the last one being undone first.
+#[derive(Default)]
+pub struct UndoStack {
+    changes: Vec<Change>,
+}
+
+impl UndoStack {
+    /// Remember `change`, or return a note for the message when its keys could not be read
+    /// before it, e.g. with a token allowed to write but not to read.
+    pub fn record(&mut self, change: Result<Change>) -> &'static str {
+        match change {
+            Ok(change) => {
+                self.push(change);
+                ""
+            }
+            Err(_) => NOT_UNDOABLE,
+        }
+    }
+
+    pub fn push(&mut self, change: Change) {
+        if change.is_empty() {
+            return;
+        }
+        if self.changes.len() >= UNDO_LIMIT {
+            self.changes.remove(0);
+        }
+        self.changes.push(change);
+    }
+
+    pub fn last(&self) -> Option<&Change> {
+        self.changes.last()
+    }
+
+    pub fn last_mut(&mut self) -> Option<&mut Change> {
+        self.changes.last_mut()
+    }
+
+    pub fn pop(&mut self) -> Option<Change> {
+        self.changes.pop()
+    }
+
+    /// Forget every change, e.g. when connecting to another vault.
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{DeleteMode, MockClient};
+
+    fn value(client: &mut VaultClient<MockClient>, path: &str) -> Option<String> {
+        client
+            .get_secret::<VaultSecret>(path, FromCache::No)
+            .ok()
+            .map(|secret| (&secret).into())
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut client = VaultClient::new(MockClient::new());
+        let mut undo = UndoStack::default();
+
+        // an update, then a new key
+        let before = read_state(&mut client, "mock/key2").unwrap();
+        client.write_secret("mock/key2", "new").unwrap();
+        let after = snapshot(Some(&VaultSecret::new("new"))).unwrap();
+        undo.push(Change::new(
+            "update of 'mock/key2'".to_owned(),
+            vec![("mock/key2".to_owned(), before, after)],
+        ));
+        client.write_secret("mock/added", "value").unwrap();
+        let added = snapshot(Some(&VaultSecret::new("value"))).unwrap();
+        undo.push(Change::new(
+            "addition of 'mock/added'".to_owned(),
+            vec![("mock/added".to_owned(), None, added)],
+        ));
+
+        let change = undo.last_mut().unwrap();
+        assert_eq!(
+            change.keys().collect::<Vec<_>>(),
+            vec![("mock/added", true)]
+        );
+        assert!(change.modified_since(&mut client).unwrap().is_empty());
+        change.revert(&mut client).unwrap();
+        assert!(change.is_empty());
+        undo.pop();
+        assert_eq!(read_state(&mut client, "mock/added").unwrap(), None);
+
+        // a change made since is detected
+        client.write_secret("mock/key2", "other").unwrap();
+        let change = undo.last_mut().unwrap();
+        assert_eq!(change.description, "update of 'mock/key2'");
+        assert_eq!(
+            change.modified_since(&mut client).unwrap(),
+            vec!["mock/key2"]
+        );
+        change.revert(&mut client).unwrap();
+        assert_eq!(value(&mut client, "mock/key2").as_deref(), Some("value"));
+        undo.pop();
+        assert!(undo.last().is_none());
+
+        // a change whose keys could not be read is not kept
+        let denied = Err(Error::Vault("permission denied".to_owned()));
+        assert_eq!(undo.record(denied), NOT_UNDOABLE);
+        assert!(undo.last().is_none());
+    }
+
+    #[test]
+    fn test_undo_transfer() {
+        let mut client = VaultClient::new(MockClient::new());
+        client.write_secret("mock/key3", "overwritten").unwrap();
+
+        // a move over an existing key
+        let plan =
+            TransferPlan::new(&mut client, TransferKind::Move, "mock/key2", "mock/key3").unwrap();
+        let mut change = Change::for_transfer(
+            &mut client,
+            "move".to_owned(),
+            &plan,
+            ConflictPolicy::Overwrite,
+        )
+        .unwrap();
+        plan.execute(&mut client, ConflictPolicy::Overwrite)
+            .unwrap();
+        assert!(change.modified_since(&mut client).unwrap().is_empty());
+        change.revert(&mut client).unwrap();
+        assert_eq!(value(&mut client, "mock/key2").as_deref(), Some("value"));
+        assert_eq!(
+            value(&mut client, "mock/key3").as_deref(),
+            Some("overwritten")
+        );
+
+        // the keys skipped are left alone
+        let plan =
+            TransferPlan::new(&mut client, TransferKind::Copy, "mock/key9/", "mock/key1/").unwrap();
+        let change =
+            Change::for_transfer(&mut client, "copy".to_owned(), &plan, ConflictPolicy::Skip)
+                .unwrap();
+        assert_eq!(
+            change.keys().collect::<Vec<_>>(),
+            vec![("mock/key1/nested", true), ("mock/key1/other", true)]
+        );
+    }
+
+    #[test]
+    fn test_undo_delete() {
+        let mut client = VaultClient::new(MockClient::new());
+        let entries = vec!["key4".to_owned(), "key9/".to_owned()];
+        let plan = DeletePlan::for_entries(&mut client, "mock/", &entries).unwrap();
+        let mut progress = 0;
+        let change =
+            Change::for_delete(&mut client, "deletion".to_owned(), &plan, |n| progress = n)
+                .unwrap();
+        assert_eq!(progress, 3);
+        plan.execute(&mut client, DeleteMode::Metadata, |_| ());
+        assert_eq!(value(&mut client, "mock/key9/other"), None);
+
+        // a key that failed to be deleted is not restored
+        let mut kept = change.without(&["mock/key4"]);
+        assert_eq!(kept.keys().count(), 2);
+        kept.revert(&mut client).unwrap();
+        assert_eq!(
+            value(&mut client, "mock/key9/nested").as_deref(),
+            Some("value")
+        );
+        assert_eq!(
+            value(&mut client, "mock/key9/other").as_deref(),
+            Some("value")
+        );
+        assert_eq!(value(&mut client, "mock/key4"), None);
+
+        // a failed restoration stays to be undone again
+        let mut client = VaultClient::new(MockClient::new().deny_writes("mock/key9/"));
+        client.delete_secret("mock/key4").unwrap();
+        let mut change = Change::new(
+            "deletion".to_owned(),
+            vec![
+                (
+                    "mock/key4".to_owned(),
+                    snapshot(Some(&VaultSecret::new("value"))).unwrap(),
+                    None,
+                ),
+                (
+                    "mock/key9/nested".to_owned(),
+                    snapshot(Some(&VaultSecret::new("value"))).unwrap(),
+                    None,
+                ),
+            ],
+        );
+        assert!(change.revert(&mut client).is_err());
+        assert_eq!(
+            change.keys().collect::<Vec<_>>(),
+            vec![("mock/key9/nested", false)]
+        );
+        assert_eq!(value(&mut client, "mock/key4").as_deref(), Some("value"));
+    }
+}